@@ -0,0 +1,196 @@
+//! Addon import strings for talent builds
+//!
+//! Uses the encoding shared by WeakAuras' original format and most addons built
+//! on Ace3 and LibDeflate: the build is serialized as a table with
+//! AceSerializer-3.0, compressed with raw deflate (LibDeflate:CompressDeflate),
+//! encoded with LibDeflate:EncodeForPrint and prefixed with "!".
+
+use crate::fetcher::TalentBuild;
+use anyhow::{Context, Result};
+use std::io::Write;
+
+/// Marks the string as deflate-compressed and print-encoded
+const ADDON_STRING_PREFIX: &str = "!";
+
+/// LibDeflate's EncodeForPrint alphabet, indexed by 6-bit value
+const PRINT_ALPHABET: &[u8; 64] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789()";
+
+/// Wrap a build into an addon import string
+/// The payload table has `class`, `spec` and `code`, plus `content` and
+/// `heroTree` when known
+pub fn to_addon_string(build: &TalentBuild) -> Result<String> {
+    let mut fields = vec![
+        ("class", build.class.clone()),
+        ("spec", build.spec.clone()),
+        ("code", build.encoded.clone()),
+    ];
+    if let Some(content) = build.content {
+        fields.push(("content", content.as_path().to_string()));
+    }
+    if let Some(hero_tree) = &build.hero_tree {
+        fields.push(("heroTree", hero_tree.clone()));
+    }
+
+    let serialized = serialize_table(&fields);
+
+    let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::best());
+    encoder
+        .write_all(serialized.as_bytes())
+        .and_then(|()| encoder.finish())
+        .map(|compressed| format!("{}{}", ADDON_STRING_PREFIX, encode_for_print(&compressed)))
+        .context("Failed to compress addon string")
+}
+
+/// AceSerializer-3.0 encoding of a single string-keyed table
+fn serialize_table(fields: &[(&str, String)]) -> String {
+    let mut serialized = String::from("^1^T");
+    for (key, value) in fields {
+        serialized.push_str("^S");
+        serialized.push_str(&escape(key));
+        serialized.push_str("^S");
+        serialized.push_str(&escape(value));
+    }
+    serialized.push_str("^t^^");
+    serialized
+}
+
+/// Escape the characters AceSerializer reserves: controls and space, "^", "~" and DEL
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c as u32 {
+            // "~^" would read as a separator, so 30 gets its own escape
+            30 => escaped.push_str("~z"),
+            n @ 0..=32 => {
+                escaped.push('~');
+                escaped.push(char::from(n as u8 + 64));
+            }
+            94 => escaped.push_str("~}"),
+            126 => escaped.push_str("~|"),
+            127 => escaped.push_str("~{"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// LibDeflate:EncodeForPrint - the bytes as a little-endian bitstream, six bits per character
+fn encode_for_print(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    let mut cache: u32 = 0;
+    let mut cache_bits = 0;
+
+    for &byte in bytes {
+        cache |= u32::from(byte) << cache_bits;
+        cache_bits += 8;
+        while cache_bits >= 6 {
+            encoded.push(char::from(PRINT_ALPHABET[(cache & 0x3f) as usize]));
+            cache >>= 6;
+            cache_bits -= 6;
+        }
+    }
+    if cache_bits > 0 {
+        encoded.push(char::from(PRINT_ALPHABET[(cache & 0x3f) as usize]));
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archon::ContentType;
+    use std::collections::HashMap;
+    use std::io::Read;
+
+    /// LibDeflate:DecodeForPrint
+    fn decode_for_print(encoded: &str) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut cache: u32 = 0;
+        let mut cache_bits = 0;
+
+        for c in encoded.bytes() {
+            let value = PRINT_ALPHABET.iter().position(|&a| a == c).expect("character outside the alphabet");
+            cache |= (value as u32) << cache_bits;
+            cache_bits += 6;
+            if cache_bits >= 8 {
+                bytes.push((cache & 0xff) as u8);
+                cache >>= 8;
+                cache_bits -= 8;
+            }
+        }
+
+        bytes
+    }
+
+    fn unescape(value: &str) -> String {
+        let mut unescaped = String::new();
+        let mut chars = value.chars();
+        while let Some(c) = chars.next() {
+            if c != '~' {
+                unescaped.push(c);
+                continue;
+            }
+            unescaped.push(match chars.next().unwrap() {
+                'z' => '\u{1e}',
+                '}' => '^',
+                '|' => '~',
+                '{' => '\u{7f}',
+                c => char::from(c as u8 - 64),
+            });
+        }
+        unescaped
+    }
+
+    /// Decode an addon string back into its table, checking every layer on the way
+    fn decode(addon_string: &str) -> HashMap<String, String> {
+        let encoded = addon_string.strip_prefix(ADDON_STRING_PREFIX).unwrap();
+
+        let mut serialized = String::new();
+        flate2::read::DeflateDecoder::new(decode_for_print(encoded).as_slice())
+            .read_to_string(&mut serialized)
+            .unwrap();
+
+        let body = serialized.strip_prefix("^1^T").unwrap().strip_suffix("^t^^").unwrap();
+        let values: Vec<String> = body
+            .split('^')
+            .skip(1)
+            .map(|token| unescape(token.strip_prefix('S').expect("only strings are written")))
+            .collect();
+        assert_eq!(values.len() % 2, 0);
+
+        values.chunks(2).map(|pair| (pair[0].clone(), pair[1].clone())).collect()
+    }
+
+    #[test]
+    fn test_addon_string_round_trips() {
+        let build = TalentBuild {
+            content: Some(ContentType::MythicPlus),
+            hero_tree: Some("Frostfire ^~ Build".to_string()),
+            ..TalentBuild::from_talent_string("mage/frost/CcQAAAAAAAAAAAAAAAAAAAAAAAAABC123+")
+        };
+
+        let addon_string = to_addon_string(&build).unwrap();
+        let table = decode(&addon_string);
+
+        assert_eq!(table["class"], "mage");
+        assert_eq!(table["spec"], "frost");
+        assert_eq!(table["code"], "CcQAAAAAAAAAAAAAAAAAAAAAAAAABC123+");
+        assert_eq!(table["content"], "mythic-plus");
+        assert_eq!(table["heroTree"], "Frostfire ^~ Build");
+    }
+
+    #[test]
+    fn test_encode_for_print_matches_libdeflate() {
+        // Three bytes fill four characters exactly; leftover bits get one more
+        assert_eq!(encode_for_print(&[0, 0, 0]), "aaaa");
+        assert_eq!(encode_for_print(&[0xff, 0xff, 0xff]), "))))");
+        assert_eq!(encode_for_print(&[1]), "ba");
+        assert_eq!(decode_for_print(&encode_for_print(b"talents")), b"talents");
+    }
+
+    #[test]
+    fn test_escape_reserved_characters() {
+        assert_eq!(escape("a b^c~d\u{1e}"), "a~`b~}c~|d~z");
+    }
+}
@@ -1,14 +1,53 @@
-use crate::wow::WowClass;
+use crate::wow::{normalize_name, Spec, WowClass};
+use anyhow::{anyhow, bail, Result};
 use chrono::Datelike;
+use serde::{Deserialize, Serialize};
 
 /// Content type for Archon.gg builds
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Serialized as its Archon path segment ("raid", "mythic-plus", "pvp")
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 #[allow(dead_code)]
 pub enum ContentType {
     Raid,
     MythicPlus,
+    Pvp,
+}
+
+impl ContentType {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match normalize_name(s).as_str() {
+            "raid" => Some(Self::Raid),
+            "mythic-plus" | "mythicplus" | "m+" => Some(Self::MythicPlus),
+            "pvp" => Some(Self::Pvp),
+            _ => None,
+        }
+    }
+
+    /// Path segment Archon.gg uses for this content type
+    pub fn as_path(&self) -> &'static str {
+        match self {
+            Self::Raid => "raid",
+            Self::MythicPlus => "mythic-plus",
+            Self::Pvp => "pvp",
+        }
+    }
+
+    /// Prefix of the generated talent names for this content type, if it has any
+    pub fn talent_name_prefix(&self) -> Option<&'static str> {
+        match self {
+            Self::Raid => Some(RAID_TALENT_PREFIX),
+            Self::MythicPlus => Some(MYTHIC_PLUS_TALENT_PREFIX),
+            Self::Pvp => None,
+        }
+    }
 }
 
+/// Start of generated raid talent names (e.g. "R-heroic-sikran_ARCT")
+const RAID_TALENT_PREFIX: &str = "R-";
+/// Start of generated Mythic+ talent names (e.g. "M+-ara-kara_ARCT")
+const MYTHIC_PLUS_TALENT_PREFIX: &str = "M+-";
+
 /// Raid difficulty levels
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RaidDifficulty {
@@ -89,10 +128,10 @@ impl TalentIdentifier {
     pub fn as_identifier(&self) -> String {
         match self {
             Self::Raid { difficulty, boss } => {
-                format!("R-{}-{}", difficulty.as_str(), boss)
+                format!("{}{}-{}", RAID_TALENT_PREFIX, difficulty.as_str(), boss)
             }
             Self::MythicPlus { dungeon } => {
-                format!("M+-{}", dungeon)
+                format!("{}{}", MYTHIC_PLUS_TALENT_PREFIX, dungeon)
             }
         }
     }
@@ -104,6 +143,7 @@ impl TalentIdentifier {
 }
 
 /// URL builder for Archon.gg talent builds
+#[derive(Debug, Clone)]
 pub struct ArchonUrlBuilder {
     base_url: String,
 }
@@ -121,6 +161,30 @@ impl ArchonUrlBuilder {
         Self::default()
     }
 
+    /// Build URLs against a different host (e.g. a mock server)
+    pub fn with_base_url(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+        }
+    }
+
+    /// Resolve a build path (e.g. "/frost/mage/raid/overview") against the base URL
+    /// Absolute URLs are returned unchanged
+    pub fn resolve(&self, url: &str) -> String {
+        if url.starts_with("http://") || url.starts_with("https://") {
+            return url.to_string();
+        }
+
+        format!("{}/{}", self.base_url.trim_end_matches('/'), url.trim_start_matches('/'))
+    }
+
+    /// Path of `url` below the base URL (e.g. "/frost/mage/raid/overview"),
+    /// or `None` if it points somewhere else
+    pub fn relative_path<'a>(&self, url: &'a str) -> Option<&'a str> {
+        let path = url.strip_prefix(self.base_url.trim_end_matches('/'))?;
+        (path.is_empty() || path.starts_with('/')).then_some(path)
+    }
+
     /// Build URL for a raid boss talent build
     /// Format: https://www.archon.gg/wow/builds/{spec}/{class}/raid/overview/{difficulty}/{encounter}
     pub fn build_raid_url(
@@ -159,12 +223,108 @@ impl ArchonUrlBuilder {
             timespan.as_str()
         )
     }
+
+    /// Build URL for the overview page of a content type
+    /// Format: https://www.archon.gg/wow/builds/{spec}/{class}/{content}/overview
+    pub fn build_overview_url(&self, class: WowClass, spec: &str, content: ContentType) -> String {
+        format!(
+            "{}/{}/{}/{}/overview",
+            self.base_url,
+            spec.to_lowercase(),
+            class.to_url_format(),
+            content.as_path()
+        )
+    }
+
+    /// Build URL for a named build variant (e.g. "cleave" or "single-target")
+    /// Format: https://www.archon.gg/wow/builds/{spec}/{class}/{content}/{variant}
+    /// Variants sit beside the overview page; the slug must be lowercase
+    /// letters, digits and hyphens
+    pub fn build_variant_url(
+        &self,
+        class: WowClass,
+        spec: &str,
+        content: ContentType,
+        variant: &str,
+    ) -> Result<String> {
+        let is_slug = !variant.is_empty()
+            && variant
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+        if !is_slug {
+            bail!("Invalid build variant slug: {:?}", variant);
+        }
+
+        Ok(format!(
+            "{}/{}/{}/{}/{}",
+            self.base_url,
+            spec.to_lowercase(),
+            class.to_url_format(),
+            content.as_path(),
+            variant
+        ))
+    }
+
+    /// Overview URL of every spec for a content type, in `Spec::all` order
+    pub fn all_overview_urls(&self, content: ContentType) -> Vec<(Spec, String)> {
+        Spec::all()
+            .iter()
+            .map(|&spec| (spec, self.build_overview_url(spec.class(), spec.name(), content)))
+            .collect()
+    }
+}
+
+/// Archon.gg overview URL of every spec for a content type, for a full refresh
+pub fn all_default_build_urls(content: ContentType) -> Vec<(Spec, String)> {
+    ArchonUrlBuilder::new().all_overview_urls(content)
+}
+
+/// Build an overview URL from loosely formatted class and spec names
+/// (e.g. "Death Knight" / "Beast Mastery"), rejecting unknown combinations
+pub fn build_url(class: &str, spec: &str, content: ContentType) -> Result<String> {
+    let wow_class =
+        WowClass::from_name(class).ok_or_else(|| anyhow!("Unknown class: {}", class))?;
+
+    let spec = normalize_name(spec);
+    if wow_class.spec_index(&spec).is_none() {
+        bail!("Unknown spec '{}' for class {}", spec, wow_class.to_url_format());
+    }
+
+    Ok(ArchonUrlBuilder::new().build_overview_url(wow_class, &spec, content))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_resolve_relative_build_paths() {
+        let builder = ArchonUrlBuilder::with_base_url("http://127.0.0.1:8080/");
+        assert_eq!(
+            builder.resolve("/frost/mage/raid/overview"),
+            "http://127.0.0.1:8080/frost/mage/raid/overview"
+        );
+        assert_eq!(
+            ArchonUrlBuilder::new().resolve("frost/mage/raid/overview"),
+            "https://www.archon.gg/wow/builds/frost/mage/raid/overview"
+        );
+        assert_eq!(
+            builder.resolve("https://www.archon.gg/wow/builds/frost/mage/raid/overview"),
+            "https://www.archon.gg/wow/builds/frost/mage/raid/overview"
+        );
+    }
+
+    #[test]
+    fn test_relative_path_below_the_base_url() {
+        let builder = ArchonUrlBuilder::new();
+        assert_eq!(
+            builder.relative_path("https://www.archon.gg/wow/builds/frost/mage/raid/overview"),
+            Some("/frost/mage/raid/overview")
+        );
+        assert_eq!(builder.relative_path("https://www.archon.gg/wow/buildsets/x"), None);
+        assert_eq!(builder.relative_path("https://mirror.example/wow/builds/frost/mage"), None);
+    }
+
     #[test]
     fn test_raid_difficulty_parsing() {
         assert_eq!(RaidDifficulty::from_str("normal"), Some(RaidDifficulty::Normal));
@@ -265,6 +425,71 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_content_type_parsing() {
+        assert_eq!(ContentType::from_str("Raid"), Some(ContentType::Raid));
+        assert_eq!(ContentType::from_str("mythic-plus"), Some(ContentType::MythicPlus));
+        assert_eq!(ContentType::from_str("M+"), Some(ContentType::MythicPlus));
+        assert_eq!(ContentType::from_str("pvp"), Some(ContentType::Pvp));
+        assert_eq!(ContentType::from_str("delves"), None);
+    }
+
+    #[test]
+    fn test_build_url_from_names() {
+        assert_eq!(
+            build_url("Mage", "Frost", ContentType::Raid).unwrap(),
+            "https://www.archon.gg/wow/builds/frost/mage/raid/overview"
+        );
+        assert_eq!(
+            build_url("Hunter", "Beast Mastery", ContentType::MythicPlus).unwrap(),
+            "https://www.archon.gg/wow/builds/beast-mastery/hunter/mythic-plus/overview"
+        );
+        assert_eq!(
+            build_url("death knight", "unholy", ContentType::Pvp).unwrap(),
+            "https://www.archon.gg/wow/builds/unholy/death-knight/pvp/overview"
+        );
+    }
+
+    #[test]
+    fn test_all_default_build_urls() {
+        let urls = all_default_build_urls(ContentType::MythicPlus);
+
+        assert_eq!(urls.len(), Spec::all().len());
+        for (spec, url) in &urls {
+            let parsed = reqwest::Url::parse(url).unwrap();
+            assert_eq!(parsed.host_str(), Some("www.archon.gg"));
+            assert_eq!(
+                parsed.path(),
+                format!("/wow/builds/{}/{}/mythic-plus/overview", spec.name(), spec.class().to_url_format())
+            );
+        }
+    }
+
+    #[test]
+    fn test_build_variant_url() {
+        let builder = ArchonUrlBuilder::new();
+        assert_eq!(
+            builder
+                .build_variant_url(WowClass::Warrior, "Fury", ContentType::MythicPlus, "single-target")
+                .unwrap(),
+            "https://www.archon.gg/wow/builds/fury/warrior/mythic-plus/single-target"
+        );
+
+        for slug in ["", "Cleave", "aoe/../x", "single target"] {
+            assert!(
+                builder.build_variant_url(WowClass::Warrior, "fury", ContentType::Raid, slug).is_err(),
+                "slug: {:?}",
+                slug
+            );
+        }
+    }
+
+    #[test]
+    fn test_build_url_rejects_unknown_names() {
+        assert!(build_url("Mage", "Holy", ContentType::Raid).is_err());
+        assert!(build_url("Bard", "frost", ContentType::Raid).is_err());
+    }
+
     #[test]
     fn test_url_double_slash_for_mythic_plus() {
         // Verify that M+ URLs have the double slash where difficulty would be
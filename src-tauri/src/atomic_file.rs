@@ -0,0 +1,113 @@
+use anyhow::{bail, Context, Result};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Distinguishes the temporary files of concurrent writes within this process
+static NEXT_TEMP_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Replace the file at `path` with `contents` so that readers (and a crash
+/// mid-write) see either the old file or the complete new one, never a partial write
+///
+/// The contents go to a temporary file next to `path`, which is flushed to disk
+/// and then renamed over the target. Renaming within a directory is atomic on
+/// POSIX, and on Windows `std::fs::rename` replaces an existing target
+/// (MoveFileEx with MOVEFILE_REPLACE_EXISTING)
+pub fn write_atomically(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let temp_path = temp_path_for(path)?;
+
+    let result = write_and_sync(&temp_path, contents.as_ref())
+        .and_then(|()| std::fs::rename(&temp_path, path).context("Failed to replace file"));
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Check that the file at `path` could be written: its directory must exist
+/// and accept new files
+/// Probes by creating and removing a temporary file of its own, so a write in
+/// progress is never disturbed
+pub fn ensure_writable(path: &Path) -> Result<()> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    if !dir.is_dir() {
+        bail!("Directory {:?} does not exist", dir);
+    }
+
+    let probe_path = temp_path_for(path)?;
+    File::create_new(&probe_path).with_context(|| format!("Directory {:?} is not writable", dir))?;
+    let _ = std::fs::remove_file(&probe_path);
+    Ok(())
+}
+
+/// Hidden sibling of `path`, unique to this process and call
+fn temp_path_for(path: &Path) -> Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("{:?} is not a file path", path))?;
+
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    let id = NEXT_TEMP_ID.fetch_add(1, Ordering::Relaxed);
+    temp_name.push(format!(".{}.{}.tmp", std::process::id(), id));
+
+    Ok(path.with_file_name(temp_name))
+}
+
+fn write_and_sync(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut file = File::create(path).context("Failed to create temporary file")?;
+    file.write_all(contents).context("Failed to write temporary file")?;
+    file.sync_all().context("Failed to flush temporary file")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_temp_paths_are_unique_per_call() {
+        let path = Path::new("TalentLoadoutsEx.lua");
+        assert_ne!(temp_path_for(path).unwrap(), temp_path_for(path).unwrap());
+    }
+
+    #[test]
+    fn test_write_atomically_replaces_contents_without_leftovers() {
+        let dir = TempDir::new();
+        let path = dir.path().join("TalentLoadoutsEx.lua");
+        std::fs::write(&path, "old contents").unwrap();
+
+        let contents = "TalentLoadoutEx = {\n".repeat(1000);
+        write_atomically(&path, &contents).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), contents);
+        let entries: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(entries.len(), 1, "stray files left behind: {:?}", entries);
+    }
+
+    #[test]
+    fn test_failed_write_leaves_target_alone() {
+        let dir = TempDir::new();
+        let missing_dir = dir.path().join("missing").join("TalentLoadoutsEx.lua");
+
+        assert!(write_atomically(&missing_dir, "contents").is_err());
+        assert!(!missing_dir.exists());
+    }
+
+    #[test]
+    fn test_ensure_writable() {
+        let dir = TempDir::new();
+        ensure_writable(&dir.path().join("TalentLoadoutsEx.lua")).unwrap();
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        let missing = dir.path().join("missing").join("TalentLoadoutsEx.lua");
+        let error = ensure_writable(&missing).unwrap_err();
+        assert!(error.to_string().contains("does not exist"), "{}", error);
+    }
+}
@@ -0,0 +1,130 @@
+use crate::atomic_file::write_atomically;
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use std::path::{Path, PathBuf};
+
+/// Number of backups kept when the config doesn't say otherwise
+pub const DEFAULT_BACKUP_COUNT: usize = 3;
+
+const BACKUP_EXTENSION: &str = "bak";
+
+/// Copy `path` to a timestamped backup next to it ("<file>.<timestamp>.bak"),
+/// then delete all but the newest `keep` backups
+/// Does nothing if `path` doesn't exist yet or `keep` is 0
+pub fn create_backup(path: &Path, keep: usize) -> Result<Option<PathBuf>> {
+    if keep == 0 || !path.exists() {
+        return Ok(None);
+    }
+
+    let file_name = file_name(path)?;
+    // Sortable timestamp, so the newest backup sorts last
+    let timestamp = Utc::now().format("%Y%m%dT%H%M%S%.9f");
+    let backup_path = path.with_file_name(format!("{}.{}.{}", file_name, timestamp, BACKUP_EXTENSION));
+
+    std::fs::copy(path, &backup_path).context("Failed to back up talent file")?;
+
+    let backups = list_backups(path)?;
+    for old_backup in backups.iter().take(backups.len().saturating_sub(keep)) {
+        std::fs::remove_file(old_backup).context("Failed to remove old backup")?;
+    }
+
+    Ok(Some(backup_path))
+}
+
+/// Backups of `path`, oldest first
+pub fn list_backups(path: &Path) -> Result<Vec<PathBuf>> {
+    let prefix = format!("{}.", file_name(path)?);
+    let suffix = format!(".{}", BACKUP_EXTENSION);
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(dir)
+        .context("Failed to list backups")?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|candidate| {
+            candidate
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(&prefix) && name.ends_with(&suffix))
+        })
+        .collect();
+    backups.sort();
+
+    Ok(backups)
+}
+
+/// Replace `path` with its most recent backup, returning the backup used
+pub fn restore_latest_backup(path: &Path) -> Result<PathBuf> {
+    let latest = match list_backups(path)?.pop() {
+        Some(latest) => latest,
+        None => bail!("No backups found for {:?}", path),
+    };
+
+    let contents = std::fs::read(&latest).context("Failed to read backup")?;
+    write_atomically(path, contents).context("Failed to restore backup")?;
+
+    Ok(latest)
+}
+
+fn file_name(path: &Path) -> Result<&str> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .with_context(|| format!("{:?} is not a file path", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_backup_holds_pre_update_contents() {
+        let dir = TempDir::new();
+        let path = dir.path().join("TalentLoadoutsEx.lua");
+
+        // Two updates, each backing up the file before overwriting it
+        std::fs::write(&path, "original").unwrap();
+        create_backup(&path, DEFAULT_BACKUP_COUNT).unwrap();
+        std::fs::write(&path, "first update").unwrap();
+        let backup = create_backup(&path, DEFAULT_BACKUP_COUNT).unwrap().unwrap();
+        std::fs::write(&path, "second update").unwrap();
+
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), "first update");
+        assert_eq!(list_backups(&path).unwrap().len(), 2);
+
+        restore_latest_backup(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "first update");
+    }
+
+    #[test]
+    fn test_only_newest_backups_are_kept() {
+        let dir = TempDir::new();
+        let path = dir.path().join("TalentLoadoutsEx.lua");
+
+        for i in 0..5 {
+            std::fs::write(&path, format!("version {}", i)).unwrap();
+            create_backup(&path, 2).unwrap();
+        }
+
+        let backups = list_backups(&path).unwrap();
+        assert_eq!(backups.len(), 2);
+        assert_eq!(std::fs::read_to_string(&backups[0]).unwrap(), "version 3");
+        assert_eq!(std::fs::read_to_string(&backups[1]).unwrap(), "version 4");
+    }
+
+    #[test]
+    fn test_nothing_to_back_up_or_restore() {
+        let dir = TempDir::new();
+        let path = dir.path().join("TalentLoadoutsEx.lua");
+
+        assert_eq!(create_backup(&path, DEFAULT_BACKUP_COUNT).unwrap(), None);
+        assert!(restore_latest_backup(&path).is_err());
+    }
+}
@@ -0,0 +1,172 @@
+use crate::fetcher::{DeadlineBatch, FetchOutcome};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A page that answered, with its talent string if it had a build
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchSuccess {
+    pub url: String,
+    pub talent_string: Option<String>,
+}
+
+/// A fetch that failed and is worth retrying later
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchFailure {
+    pub url: String,
+    pub reason: String,
+    /// HTTP status when Archon answered with an error, so 404s can be told from 403s
+    #[serde(default)]
+    pub status: Option<u16>,
+}
+
+/// Results of a batch, split into pages that answered and fetches that failed
+/// Pages without a build (no data yet, no talent link) count as successes
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatchSummary {
+    pub successes: Vec<BatchSuccess>,
+    pub failures: Vec<FetchFailure>,
+}
+
+impl BatchSummary {
+    /// Sort `fetch_many` results into successes and failures, keeping their order
+    pub fn from_results(results: Vec<(String, Result<FetchOutcome>)>) -> Self {
+        let mut summary = Self::default();
+
+        for (url, result) in results {
+            let status = match &result {
+                Ok(outcome) => outcome.http_status().map(|status| status.as_u16()),
+                Err(_) => None,
+            };
+            let reason = match result {
+                Ok(outcome @ FetchOutcome::HttpError(_)) => {
+                    format!("HTTP {}", outcome.http_error_message().unwrap_or_default())
+                }
+                Ok(FetchOutcome::Network(e)) => format!("Network error: {}", e),
+                Ok(FetchOutcome::Blocked) => "Blocked by a Cloudflare challenge".to_string(),
+                Ok(FetchOutcome::OfflineMiss) => "Not cached while offline".to_string(),
+                Ok(FetchOutcome::BudgetExceeded) => "Download budget used up".to_string(),
+                Ok(FetchOutcome::UnexpectedContentType(content_type)) => {
+                    format!("Unexpected content type {}", content_type)
+                }
+                Ok(FetchOutcome::UnreadableBody(reason)) => format!("Unreadable response: {}", reason),
+                Ok(FetchOutcome::SpecMismatch { requested, got }) => {
+                    format!("Build is for {} instead of {}", got.as_wowhead_path(), requested.as_wowhead_path())
+                }
+                Ok(outcome) => {
+                    summary.successes.push(BatchSuccess {
+                        url,
+                        talent_string: outcome.into_talent_string(),
+                    });
+                    continue;
+                }
+                Err(e) => format!("{:#}", e),
+            };
+
+            summary.failures.push(FetchFailure { url, reason, status });
+        }
+
+        summary
+    }
+
+    /// Same as `from_results`, with the URLs the deadline cut off counted as
+    /// failures so a retry picks them up
+    pub fn from_deadline_batch(batch: DeadlineBatch) -> Self {
+        let mut summary = Self::from_results(batch.completed);
+        summary.failures.extend(batch.unfinished.into_iter().map(|url| FetchFailure {
+            url,
+            reason: "Not fetched before the deadline".to_string(),
+            status: None,
+        }));
+        summary
+    }
+
+    /// URLs of the failed fetches
+    #[allow(dead_code)]
+    pub fn failed_urls(&self) -> Vec<String> {
+        self.failures.iter().map(|failure| failure.url.clone()).collect()
+    }
+
+    /// Write the failures to `path` as JSON (an empty list when nothing failed)
+    pub fn save_failures(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).context("Failed to create failures directory")?;
+        }
+
+        let contents = serde_json::to_string_pretty(&self.failures)?;
+        std::fs::write(path, contents).context("Failed to write failures file")?;
+        Ok(())
+    }
+}
+
+/// Failures saved by `BatchSummary::save_failures`; a missing file means none
+pub fn load_failures(path: &Path) -> Result<Vec<FetchFailure>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = std::fs::read_to_string(path).context("Failed to read failures file")?;
+    serde_json::from_str(&contents).context("Failed to parse failures file")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{fetcher_builder, MockResponse, MockServer, TempDir};
+
+    #[tokio::test]
+    async fn test_failed_urls_are_persisted() {
+        let server = MockServer::start().await;
+        server.respond(
+            "/found",
+            MockResponse::html(r#"<a href="https://www.wowhead.com/talent-calc/blizzard/mage/frost/CcQAAAAAAAAAAAAAAAAAAAAAAAAABC123">Build</a>"#),
+        );
+        server.respond("/no-data", MockResponse::status(500));
+        server.respond("/broken", MockResponse::status(403));
+
+        let fetcher = fetcher_builder().max_retries(0).build().unwrap();
+        let urls = vec![server.url("/found"), server.url("/no-data"), server.url("/broken")];
+        let summary = BatchSummary::from_results(fetcher.fetch_many(&urls).await);
+
+        assert_eq!(summary.successes.len(), 2);
+        assert_eq!(
+            summary.successes[0].talent_string.as_deref(),
+            Some("mage/frost/CcQAAAAAAAAAAAAAAAAAAAAAAAAABC123")
+        );
+        assert_eq!(summary.failed_urls(), vec![server.url("/broken")]);
+
+        let dir = TempDir::new();
+        let path = dir.path().join("failures.json");
+        summary.save_failures(&path).unwrap();
+
+        let failures = load_failures(&path).unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].url, server.url("/broken"));
+        assert_eq!(failures[0].reason, "HTTP 403 — try later");
+        assert_eq!(failures[0].status, Some(403));
+    }
+
+    #[tokio::test]
+    async fn test_fetches_cut_off_by_the_deadline_are_failures() {
+        let server = MockServer::start().await;
+        server.respond("/no-data", MockResponse::status(500));
+        server.respond("/slow", MockResponse::status(500).delay(std::time::Duration::from_secs(2)));
+
+        let fetcher = fetcher_builder().max_retries(0).build().unwrap();
+        let urls = vec![server.url("/no-data"), server.url("/slow")];
+        let batch = fetcher.fetch_many_within(&urls, std::time::Duration::from_millis(300)).await;
+        let summary = BatchSummary::from_deadline_batch(batch);
+
+        assert_eq!(summary.successes.len(), 1);
+        assert_eq!(summary.failed_urls(), vec![server.url("/slow")]);
+        assert_eq!(summary.failures[0].reason, "Not fetched before the deadline");
+    }
+
+    #[test]
+    fn test_missing_failures_file_is_empty() {
+        let dir = TempDir::new();
+        assert!(load_failures(&dir.path().join("failures.json")).unwrap().is_empty());
+    }
+}
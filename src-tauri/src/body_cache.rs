@@ -0,0 +1,63 @@
+//! Raw build page bodies kept on disk, so the parser can be re-run over the
+//! exact HTML a fetch saw after Archon changes its markup
+
+use crate::atomic_file::write_atomically;
+use crate::cache::fnv1a;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Directory of response bodies, one file per URL named after the URL's hash
+pub struct BodyCache {
+    dir: PathBuf,
+}
+
+impl BodyCache {
+    /// Store bodies in `dir`, creating it on the first write
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path_for(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}.html", fnv1a(url.as_bytes())))
+    }
+
+    /// Replace the stored body of `url`
+    pub fn store(&self, url: &str, body: &str) -> Result<()> {
+        std::fs::create_dir_all(&self.dir).context("Failed to create body cache directory")?;
+        write_atomically(&self.path_for(url), body)
+    }
+
+    /// Stored body of `url`, if it was ever fetched
+    pub fn load(&self, url: &str) -> Result<Option<String>> {
+        read_if_exists(&self.path_for(url))
+    }
+}
+
+fn read_if_exists(path: &Path) -> Result<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(body) => Ok(Some(body)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read cached body {:?}", path)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_bodies_are_stored_per_url() {
+        let dir = TempDir::new();
+        let cache = BodyCache::new(dir.path().join("bodies"));
+
+        assert_eq!(cache.load("https://archon.gg/a").unwrap(), None);
+
+        cache.store("https://archon.gg/a", "<p>first</p>").unwrap();
+        cache.store("https://archon.gg/b", "<p>other</p>").unwrap();
+        cache.store("https://archon.gg/a", "<p>second</p>").unwrap();
+
+        assert_eq!(cache.load("https://archon.gg/a").unwrap().as_deref(), Some("<p>second</p>"));
+        assert_eq!(cache.load("https://archon.gg/b").unwrap().as_deref(), Some("<p>other</p>"));
+    }
+}
@@ -0,0 +1,106 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+/// Word that introduces the date on Archon pages ("Last updated 2 days ago")
+const UPDATED_MARKER: &str = "updated";
+
+/// Absolute date formats seen after the marker, tried in order
+const DATE_FORMATS: &[&str] = &["%B %d, %Y", "%b %d, %Y", "%d %B %Y", "%d %b %Y", "%Y-%m-%d", "%m/%d/%Y"];
+
+/// Longest absolute date, in words ("March 3, 2025")
+const MAX_DATE_WORDS: usize = 3;
+
+/// Find a "last updated" date in page text, either relative to `now`
+/// ("2 days ago", "yesterday") or absolute ("March 3, 2025")
+pub fn parse_last_updated(text: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let text = text.to_lowercase();
+
+    text.match_indices(UPDATED_MARKER).find_map(|(index, _)| {
+        let rest = text[index + UPDATED_MARKER.len()..].trim_start_matches([':', ' ', '\n', '\t']);
+        let rest = rest.strip_prefix("on ").unwrap_or(rest);
+        let words: Vec<&str> = rest.split_whitespace().take(MAX_DATE_WORDS).collect();
+
+        parse_relative(&words, now).or_else(|| parse_absolute(&words))
+    })
+}
+
+/// Parse a date or timestamp attribute such as `<time datetime="...">`
+pub fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    DateTime::parse_from_rfc3339(value)
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .ok()
+        .or_else(|| parse_absolute(&[value]))
+}
+
+fn parse_relative(words: &[&str], now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let words: Vec<&str> = words.iter().map(|word| word.trim_end_matches(['.', ',', ')'])).collect();
+
+    match words.as_slice() {
+        ["just", "now", ..] | ["today", ..] => return Some(now),
+        ["yesterday", ..] => return Some(now - Duration::days(1)),
+        _ => {}
+    }
+
+    let [amount, unit, "ago", ..] = words.as_slice() else {
+        return None;
+    };
+    let amount: i32 = match *amount {
+        "a" | "an" => 1,
+        amount => amount.parse().ok()?,
+    };
+    let unit = match unit.trim_end_matches('s') {
+        "second" | "sec" => Duration::seconds(1),
+        "minute" | "min" => Duration::minutes(1),
+        "hour" | "hr" => Duration::hours(1),
+        "day" => Duration::days(1),
+        "week" => Duration::weeks(1),
+        "month" => Duration::days(30),
+        "year" => Duration::days(365),
+        _ => return None,
+    };
+
+    now.checked_sub_signed(unit.checked_mul(amount)?)
+}
+
+fn parse_absolute(words: &[&str]) -> Option<DateTime<Utc>> {
+    // Longest candidate first, so "march 3, 2025" wins over "march 3,"
+    (1..=words.len()).rev().find_map(|count| {
+        let candidate = words[..count].join(" ");
+        let candidate = candidate.trim_end_matches(['.', ',', ')']);
+        DATE_FORMATS
+            .iter()
+            .find_map(|format| NaiveDate::parse_from_str(candidate, format).ok())
+            .map(|date| date.and_hms_opt(0, 0, 0).expect("midnight is valid").and_utc())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 3, 10, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_relative_phrases() {
+        assert_eq!(parse_last_updated("Last updated 2 days ago.", now()), Some(now() - Duration::days(2)));
+        assert_eq!(parse_last_updated("Updated an hour ago", now()), Some(now() - Duration::hours(1)));
+        assert_eq!(parse_last_updated("updated: yesterday", now()), Some(now() - Duration::days(1)));
+    }
+
+    #[test]
+    fn test_absolute_dates() {
+        let expected = Utc.with_ymd_and_hms(2025, 3, 3, 0, 0, 0).unwrap();
+        assert_eq!(parse_last_updated("Last Updated: March 3, 2025. Builds", now()), Some(expected));
+        assert_eq!(parse_last_updated("updated on 2025-03-03", now()), Some(expected));
+        assert_eq!(parse_timestamp("2025-03-03T00:00:00Z"), Some(expected));
+    }
+
+    #[test]
+    fn test_no_date() {
+        assert_eq!(parse_last_updated("Recently updated builds for every spec", now()), None);
+        assert_eq!(parse_last_updated("Frost Mage build", now()), None);
+    }
+}
@@ -0,0 +1,152 @@
+//! Shareable JSON manifest of talent builds
+//!
+//! Schema version 1:
+//!
+//! ```json
+//! {
+//!   "schemaVersion": 1,
+//!   "generatedAt": "2025-03-10T12:00:00Z",
+//!   "builds": [
+//!     { "class": "mage", "spec": "frost", "content": "raid", "name": "Single Target", "code": "CAEAAAAA..." }
+//!   ]
+//! }
+//! ```
+//!
+//! `content` is "raid", "mythic-plus", "pvp" or null when unknown, and `name`
+//! is the build title from the page, left out when there is none. Only these
+//! fields are shared; page details such as popularity are not. Fields may be
+//! added without a version bump, but renaming or removing one needs a new version.
+
+use crate::archon::ContentType;
+use crate::atomic_file::write_atomically;
+use crate::fetcher::TalentBuild;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Version written by `export_builds`, and the only one `import_builds` reads
+pub const MANIFEST_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BuildManifest {
+    schema_version: u32,
+    generated_at: DateTime<Utc>,
+    builds: Vec<ManifestBuild>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ManifestBuild {
+    class: String,
+    spec: String,
+    content: Option<ContentType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    code: String,
+}
+
+/// Read before the rest so an unknown version is reported as such,
+/// rather than as whatever field it no longer matches
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ManifestHeader {
+    schema_version: u32,
+}
+
+/// Write `builds` to `path` as a manifest
+pub fn export_builds(builds: &[TalentBuild], path: &Path) -> Result<()> {
+    let manifest = BuildManifest {
+        schema_version: MANIFEST_SCHEMA_VERSION,
+        generated_at: Utc::now(),
+        builds: builds
+            .iter()
+            .map(|build| ManifestBuild {
+                class: build.class.clone(),
+                spec: build.spec.clone(),
+                content: build.content,
+                name: build.name.clone(),
+                code: build.encoded.clone(),
+            })
+            .collect(),
+    };
+
+    let contents = serde_json::to_string_pretty(&manifest)?;
+    write_atomically(path, contents).context("Failed to write build manifest")
+}
+
+/// Read the builds from a manifest written by `export_builds`
+pub fn import_builds(path: &Path) -> Result<Vec<TalentBuild>> {
+    let contents = std::fs::read_to_string(path).context("Failed to read build manifest")?;
+
+    let header: ManifestHeader = serde_json::from_str(&contents).context("Not a build manifest")?;
+    if header.schema_version != MANIFEST_SCHEMA_VERSION {
+        bail!(
+            "Unsupported build manifest version {} (expected {})",
+            header.schema_version,
+            MANIFEST_SCHEMA_VERSION
+        );
+    }
+
+    let manifest: BuildManifest = serde_json::from_str(&contents).context("Failed to parse build manifest")?;
+    Ok(manifest
+        .builds
+        .into_iter()
+        .map(|build| TalentBuild {
+            class: build.class,
+            spec: build.spec,
+            content: build.content,
+            name: build.name,
+            encoded: build.code,
+            hero_tree: None,
+            last_updated: None,
+            popularity: None,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_export_then_import_round_trips() {
+        let dir = TempDir::new();
+        let path = dir.path().join("builds.json");
+        let builds = vec![
+            TalentBuild {
+                content: Some(ContentType::Raid),
+                name: Some("Single Target".to_string()),
+                ..TalentBuild::from_talent_string("mage/frost/CcQAAAAAAAAAAAAAAAAAAAAAAAAABC123")
+            },
+            TalentBuild {
+                content: Some(ContentType::MythicPlus),
+                ..TalentBuild::from_talent_string("warrior/fury/CcQAAAAAAAAAAAAAAAAAAAAAAAAXYZ789")
+            },
+            TalentBuild::from_talent_string("priest/CcQAAAAAAAAAAAAAAAAAAAAAAAAQRS456"),
+        ];
+
+        export_builds(&builds, &path).unwrap();
+
+        assert_eq!(import_builds(&path).unwrap(), builds);
+        let manifest: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(manifest["schemaVersion"], 1);
+        assert_eq!(manifest["builds"][1]["content"], "mythic-plus");
+        assert_eq!(manifest["builds"][0]["name"], "Single Target");
+        assert!(manifest["builds"][1].get("name").is_none());
+    }
+
+    #[test]
+    fn test_unknown_schema_version_is_rejected() {
+        let dir = TempDir::new();
+        let path = dir.path().join("builds.json");
+        std::fs::write(&path, r#"{"schemaVersion": 2, "shared": []}"#).unwrap();
+
+        let error = import_builds(&path).unwrap_err();
+        assert_eq!(error.to_string(), "Unsupported build manifest version 2 (expected 1)");
+
+        std::fs::write(&path, r#"{"builds": []}"#).unwrap();
+        assert!(import_builds(&path).is_err());
+    }
+}
@@ -0,0 +1,440 @@
+//! JSON file cache of fetched talent strings
+//!
+//! Many build URLs resolve to the same talent code, so the file is content
+//! addressed: each URL's entry stores the hash of its code, and every unique
+//! code is stored once under that hash. Caches written in the older flat
+//! `url -> entry` layout are migrated when opened.
+
+use crate::atomic_file::write_atomically;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// A talent string fetched from a build page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheEntry {
+    pub talent_string: String,
+    pub fetched_at: DateTime<Utc>,
+    /// ETag of the response the talent string came from, for revalidation
+    #[serde(default)]
+    pub etag: Option<String>,
+    /// Popularity shown on the page, in percent
+    #[serde(default)]
+    pub popularity: Option<f32>,
+    /// Expiry the response gave (Cache-Control max-age or Expires); `None` uses the TTL
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl CacheEntry {
+    /// Whether the entry hasn't expired yet, or without an expiry, is younger than the given TTL
+    pub fn is_fresh(&self, ttl: Duration) -> bool {
+        if let Some(expires_at) = self.expires_at {
+            return Utc::now() < expires_at;
+        }
+
+        let age = Utc::now() - self.fetched_at;
+        age.to_std().map(|age| age < ttl).unwrap_or(true)
+    }
+}
+
+/// What the cache file stores per URL; the talent string lives in `CacheFile::codes`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UrlEntry {
+    code_hash: String,
+    fetched_at: DateTime<Utc>,
+    #[serde(default)]
+    etag: Option<String>,
+    #[serde(default)]
+    popularity: Option<f32>,
+    #[serde(default)]
+    expires_at: Option<DateTime<Utc>>,
+}
+
+/// On-disk layout: URL entries referencing talent strings by hash
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CacheFile {
+    urls: HashMap<String, UrlEntry>,
+    codes: HashMap<String, String>,
+}
+
+impl CacheFile {
+    fn from_entries(entries: HashMap<String, CacheEntry>) -> Self {
+        let mut file = Self::default();
+        for (url, entry) in entries {
+            file.insert(url, entry);
+        }
+        file
+    }
+
+    fn get(&self, url: &str) -> Option<CacheEntry> {
+        let entry = self.urls.get(url)?;
+        Some(CacheEntry {
+            talent_string: self.codes.get(&entry.code_hash)?.clone(),
+            fetched_at: entry.fetched_at,
+            etag: entry.etag.clone(),
+            popularity: entry.popularity,
+            expires_at: entry.expires_at,
+        })
+    }
+
+    fn insert(&mut self, url: String, entry: CacheEntry) {
+        let code_hash = self.store_code(entry.talent_string);
+        self.urls.insert(
+            url,
+            UrlEntry {
+                code_hash,
+                fetched_at: entry.fetched_at,
+                etag: entry.etag,
+                popularity: entry.popularity,
+                expires_at: entry.expires_at,
+            },
+        );
+        self.prune_codes();
+    }
+
+    /// Key under which `code` is stored, adding it if it's new
+    /// On a hash collision with a different code, a numbered suffix is appended
+    fn store_code(&mut self, code: String) -> String {
+        let hash = format!("{:016x}", fnv1a(code.as_bytes()));
+        let mut key = hash.clone();
+        let mut suffix = 1;
+        loop {
+            match self.codes.get(&key) {
+                Some(stored) if *stored == code => return key,
+                Some(_) => {
+                    key = format!("{}-{}", hash, suffix);
+                    suffix += 1;
+                }
+                None => {
+                    self.codes.insert(key.clone(), code);
+                    return key;
+                }
+            }
+        }
+    }
+
+    /// Drop codes no URL refers to anymore
+    fn prune_codes(&mut self) {
+        let referenced: HashSet<&str> = self.urls.values().map(|entry| entry.code_hash.as_str()).collect();
+        self.codes.retain(|hash, _| referenced.contains(hash.as_str()));
+    }
+}
+
+/// Cache files as found on disk, newest layout first
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredCache {
+    ContentAddressed(CacheFile),
+    /// Before deduplication: every URL stored its own talent string
+    Flat(HashMap<String, CacheEntry>),
+}
+
+/// 64-bit FNV-1a, which is stable across builds unlike `DefaultHasher`
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    bytes
+        .iter()
+        .fold(OFFSET_BASIS, |hash, &byte| (hash ^ byte as u64).wrapping_mul(PRIME))
+}
+
+/// JSON file cache of fetched talent strings, keyed by build URL
+/// Inserts only change the entries in memory; `flush` writes them to the file
+pub struct DiskCache {
+    path: PathBuf,
+    entries: Mutex<CacheFile>,
+    /// Entries changed since the file was last written
+    dirty: AtomicBool,
+    /// A flush has been scheduled but hasn't started yet
+    flush_pending: AtomicBool,
+    /// Held while the file is written or deleted, so writes land in snapshot order
+    file_lock: Mutex<()>,
+}
+
+impl DiskCache {
+    /// Open the cache file at `path`, starting empty if it doesn't exist yet
+    /// An unreadable cache is discarded since everything in it can be re-fetched
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+
+        let entries = match Self::load(&path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                tracing::warn!(path = ?path, "Ignoring unreadable talent cache: {:#}", e);
+                CacheFile::default()
+            }
+        };
+
+        Self {
+            path,
+            entries: Mutex::new(entries),
+            dirty: AtomicBool::new(false),
+            flush_pending: AtomicBool::new(false),
+            file_lock: Mutex::new(()),
+        }
+    }
+
+    fn load(path: &Path) -> Result<CacheFile> {
+        if !path.exists() {
+            return Ok(CacheFile::default());
+        }
+
+        let contents = std::fs::read_to_string(path).context("Failed to read cache file")?;
+        let stored: StoredCache = serde_json::from_str(&contents).context("Failed to parse cache file")?;
+        Ok(match stored {
+            StoredCache::ContentAddressed(file) => file,
+            StoredCache::Flat(entries) => CacheFile::from_entries(entries),
+        })
+    }
+
+    /// Cached entry for `url`, regardless of age
+    pub fn get(&self, url: &str) -> Option<CacheEntry> {
+        self.entries.lock().unwrap().get(url)
+    }
+
+    /// Cached talent string for `url`, if it is younger than `ttl`
+    #[allow(dead_code)]
+    pub fn get_fresh(&self, url: &str, ttl: Duration) -> Option<String> {
+        self.get(url)
+            .filter(|entry| entry.is_fresh(ttl))
+            .map(|entry| entry.talent_string)
+    }
+
+    /// Store a freshly fetched (or revalidated) talent string
+    /// It reaches the file with the next `flush`
+    pub fn insert(
+        &self,
+        url: &str,
+        talent_string: &str,
+        etag: Option<String>,
+        popularity: Option<f32>,
+        expires_at: Option<DateTime<Utc>>,
+    ) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.insert(
+            url.to_string(),
+            CacheEntry {
+                talent_string: talent_string.to_string(),
+                fetched_at: Utc::now(),
+                etag,
+                popularity,
+                expires_at,
+            },
+        );
+        self.dirty.store(true, Ordering::SeqCst);
+    }
+
+    /// Claim the next flush for the caller to schedule
+    /// Returns false if a scheduled flush hasn't started yet, which will pick up this change too
+    pub fn schedule_flush(&self) -> bool {
+        !self.flush_pending.swap(true, Ordering::SeqCst)
+    }
+
+    /// Write the entries to the file if they changed since the last flush
+    /// Blocks on file IO, so async code should run it on a blocking thread
+    pub fn flush(&self) -> Result<()> {
+        let _file = self.file_lock.lock().unwrap();
+        self.flush_pending.store(false, Ordering::SeqCst);
+        if !self.dirty.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        // Serialized under the entries lock, written without it so inserts don't wait on the disk
+        let contents = serde_json::to_string(&*self.entries.lock().unwrap())?;
+        self.save(contents).inspect_err(|_| self.dirty.store(true, Ordering::SeqCst))
+    }
+
+    /// Forget every entry and delete the cache file, returning the URLs that were cached
+    /// Succeeds without doing anything if nothing is cached
+    pub fn clear(&self) -> Result<Vec<String>> {
+        let _file = self.file_lock.lock().unwrap();
+        let mut entries = self.entries.lock().unwrap();
+        let removed = std::mem::take(&mut *entries).urls.into_keys().collect();
+        self.dirty.store(false, Ordering::SeqCst);
+
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).context("Failed to delete cache file")
+            }
+            _ => Ok(removed),
+        }
+    }
+
+    fn save(&self, contents: String) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).context("Failed to create cache directory")?;
+        }
+
+        // Replaced atomically so a crash mid-save can't leave a truncated cache
+        write_atomically(&self.path, contents).context("Failed to write cache file")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_cache_round_trips_through_disk() {
+        let dir = TempDir::new();
+        let path = dir.path().join("cache.json");
+
+        let cache = DiskCache::open(&path);
+        cache.insert("https://archon.gg/a", "mage/frost/ABC123", Some("\"v1\"".to_string()), Some(62.5), None);
+        cache.flush().unwrap();
+
+        let reopened = DiskCache::open(&path);
+        assert_eq!(
+            reopened.get_fresh("https://archon.gg/a", Duration::from_secs(60)),
+            Some("mage/frost/ABC123".to_string())
+        );
+        let entry = reopened.get("https://archon.gg/a").unwrap();
+        assert_eq!(entry.etag, Some("\"v1\"".to_string()));
+        assert_eq!(entry.popularity, Some(62.5));
+        assert_eq!(reopened.get_fresh("https://archon.gg/b", Duration::from_secs(60)), None);
+    }
+
+    #[test]
+    fn test_inserts_reach_the_file_on_flush() {
+        let dir = TempDir::new();
+        let path = dir.path().join("cache.json");
+
+        let cache = DiskCache::open(&path);
+        cache.insert("https://archon.gg/a", "mage/frost/ABC123", None, None, None);
+        assert!(!path.exists());
+
+        cache.flush().unwrap();
+        assert!(DiskCache::open(&path).get("https://archon.gg/a").is_some());
+
+        // Nothing changed since, so the file is left alone
+        std::fs::remove_file(&path).unwrap();
+        cache.flush().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_only_the_first_flush_request_is_scheduled() {
+        let dir = TempDir::new();
+        let cache = DiskCache::open(dir.path().join("cache.json"));
+
+        assert!(cache.schedule_flush());
+        assert!(!cache.schedule_flush());
+        cache.flush().unwrap();
+        assert!(cache.schedule_flush());
+    }
+
+    #[test]
+    fn test_expired_entries_are_ignored() {
+        let dir = TempDir::new();
+        let cache = DiskCache::open(dir.path().join("cache.json"));
+        cache.insert("https://archon.gg/a", "mage/frost/ABC123", None, None, None);
+
+        assert_eq!(cache.get_fresh("https://archon.gg/a", Duration::ZERO), None);
+    }
+
+    #[test]
+    fn test_identical_codes_are_stored_once() {
+        let dir = TempDir::new();
+        let path = dir.path().join("cache.json");
+
+        let cache = DiskCache::open(&path);
+        cache.insert("https://archon.gg/a", "mage/frost/ABC123", None, Some(40.0), None);
+        cache.insert("https://archon.gg/b", "mage/frost/ABC123", None, Some(60.0), None);
+        cache.flush().unwrap();
+
+        let file: CacheFile = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(file.urls.len(), 2);
+        assert_eq!(file.codes.len(), 1);
+
+        let reopened = DiskCache::open(&path);
+        assert_eq!(reopened.get("https://archon.gg/a").unwrap().popularity, Some(40.0));
+        assert_eq!(reopened.get("https://archon.gg/b").unwrap().talent_string, "mage/frost/ABC123");
+
+        // Replacing a URL's code drops the old one once nothing refers to it
+        reopened.insert("https://archon.gg/a", "mage/frost/XYZ789", None, None, None);
+        reopened.insert("https://archon.gg/b", "mage/frost/XYZ789", None, None, None);
+        reopened.flush().unwrap();
+        let file: CacheFile = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(file.codes.values().collect::<Vec<_>>(), vec!["mage/frost/XYZ789"]);
+    }
+
+    #[test]
+    fn test_hash_collisions_keep_both_codes() {
+        let mut file = CacheFile::default();
+        let hash = format!("{:016x}", fnv1a(b"mage/frost/ABC123"));
+        // Pretend a different code already took this hash
+        file.codes.insert(hash.clone(), "mage/frost/OTHER".to_string());
+
+        let key = file.store_code("mage/frost/ABC123".to_string());
+        assert_ne!(key, hash);
+        assert_eq!(file.codes[&key], "mage/frost/ABC123");
+        assert_eq!(file.codes[&hash], "mage/frost/OTHER");
+    }
+
+    #[test]
+    fn test_flat_cache_is_migrated() {
+        let dir = TempDir::new();
+        let path = dir.path().join("cache.json");
+        std::fs::write(
+            &path,
+            r#"{"https://archon.gg/a": {"talentString": "mage/frost/ABC123", "fetchedAt": "2024-01-01T00:00:00Z", "etag": "\"v1\""}}"#,
+        )
+        .unwrap();
+
+        let cache = DiskCache::open(&path);
+        let entry = cache.get("https://archon.gg/a").unwrap();
+        assert_eq!(entry.talent_string, "mage/frost/ABC123");
+        assert_eq!(entry.etag, Some("\"v1\"".to_string()));
+    }
+
+    #[test]
+    fn test_clear_removes_entries_and_file() {
+        let dir = TempDir::new();
+        let path = dir.path().join("cache.json");
+        let cache = DiskCache::open(&path);
+        cache.insert("https://archon.gg/a", "mage/frost/ABC123", Some("\"v1\"".to_string()), None, None);
+        cache.insert("https://archon.gg/b", "mage/frost/XYZ789", None, None, None);
+        cache.flush().unwrap();
+
+        assert_eq!(cache.clear().unwrap().len(), 2);
+        assert!(cache.get("https://archon.gg/a").is_none());
+        assert!(!path.exists());
+        assert!(DiskCache::open(&path).get("https://archon.gg/b").is_none());
+
+        // Nothing left to clear
+        assert!(cache.clear().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_expiry_overrides_the_ttl() {
+        let dir = TempDir::new();
+        let cache = DiskCache::open(dir.path().join("cache.json"));
+        let in_a_minute = Utc::now() + chrono::Duration::seconds(60);
+        cache.insert("https://archon.gg/a", "mage/frost/ABC123", None, None, Some(in_a_minute));
+        cache.insert("https://archon.gg/b", "mage/frost/ABC123", None, None, Some(Utc::now()));
+
+        // Fresh despite a zero TTL, and stale despite a long one
+        assert!(cache.get_fresh("https://archon.gg/a", Duration::ZERO).is_some());
+        assert!(cache.get_fresh("https://archon.gg/b", Duration::from_secs(3600)).is_none());
+    }
+
+    #[test]
+    fn test_corrupt_cache_starts_empty() {
+        let dir = TempDir::new();
+        let path = dir.path().join("cache.json");
+        std::fs::write(&path, "not json").unwrap();
+
+        let cache = DiskCache::open(&path);
+        assert_eq!(cache.get_fresh("https://archon.gg/a", Duration::from_secs(60)), None);
+    }
+}
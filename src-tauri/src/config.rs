@@ -1,4 +1,9 @@
-use serde::{Deserialize, Serialize};
+use crate::archon::ContentType;
+use crate::atomic_file::write_atomically;
+use crate::wow::Spec;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 /// Configuration structure for the Archon talent fetcher
@@ -25,6 +30,37 @@ pub struct Config {
     /// Path to TalentLoadoutsEx.lua file
     /// Example: "/Applications/World of Warcraft/_retail_/WTF/Account/400793633#1/SavedVariables/TalentLoadoutsEx.lua"
     pub output_path: PathBuf,
+
+    /// How many backups of the output file to keep (0 disables backups)
+    #[serde(default = "default_backup_count")]
+    pub backup_count: usize,
+
+    /// Builds used by fewer top players than this (in percent) are skipped,
+    /// keeping the build already in the file
+    #[serde(default)]
+    pub min_popularity: Option<f32>,
+
+    /// Specs fetched fewer than this many hours ago are skipped, so a refresh
+    /// only touches stale ones. When unset every spec is fetched
+    #[serde(default)]
+    pub refresh_interval_hours: Option<u64>,
+
+    /// Build page URLs to fetch instead of the default Archon overview URLs,
+    /// keyed by "class/spec" (e.g. "mage/frost") and content type. An override
+    /// is fetched for every boss or dungeon build of that content, and for its
+    /// overview build when filling in missing specs
+    #[serde(default)]
+    pub spec_url_overrides: BTreeMap<String, BTreeMap<ContentType, String>>,
+
+    /// Content types in order of preference (e.g. ["mythic-plus", "raid"]).
+    /// When filling in a missing overview build Archon has no data for, the
+    /// content types after it are tried in turn
+    #[serde(default)]
+    pub content_preference: Vec<ContentType>,
+}
+
+fn default_backup_count() -> usize {
+    crate::backup::DEFAULT_BACKUP_COUNT
 }
 
 /// Character configuration
@@ -39,6 +75,63 @@ pub struct Character {
 
     /// List of specialization names in lowercase (e.g., ["frost", "unholy"])
     pub specializations: Vec<String>,
+
+    /// When the builds of each specialization were last fetched, keyed by spec name
+    /// and then content type, so raid and Mythic+ builds are refreshed separately
+    #[serde(default, deserialize_with = "deserialize_last_fetched")]
+    pub last_fetched: BTreeMap<String, FetchTimes>,
+}
+
+/// When each content type's builds of one spec were last fetched
+pub type FetchTimes = BTreeMap<ContentType, DateTime<Utc>>;
+
+/// `last_fetched` as written before it was keyed by content type
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredFetchTimes {
+    PerContent(FetchTimes),
+    /// A single timestamp for the whole spec, read as when its raid builds were fetched
+    Spec(DateTime<Utc>),
+}
+
+fn deserialize_last_fetched<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, FetchTimes>, D::Error> {
+    let stored = BTreeMap::<String, StoredFetchTimes>::deserialize(deserializer)?;
+    Ok(stored
+        .into_iter()
+        .map(|(spec, times)| {
+            let times = match times {
+                StoredFetchTimes::PerContent(times) => times,
+                StoredFetchTimes::Spec(fetched_at) => FetchTimes::from([(ContentType::Raid, fetched_at)]),
+            };
+            (spec, times)
+        })
+        .collect())
+}
+
+impl Character {
+    /// Whether the `content` builds of `spec` were fetched less than `interval` before `now`
+    pub fn is_fetched_within(
+        &self,
+        spec: &str,
+        content: ContentType,
+        interval: chrono::Duration,
+        now: DateTime<Utc>,
+    ) -> bool {
+        self.last_fetched
+            .get(spec)
+            .and_then(|times| times.get(&content))
+            .is_some_and(|fetched_at| now.signed_duration_since(*fetched_at) < interval)
+    }
+
+    /// Record that the `content` builds of `spec` were fetched at `fetched_at`
+    pub fn mark_fetched(&mut self, spec: &str, content: ContentType, fetched_at: DateTime<Utc>) {
+        self.last_fetched
+            .entry(spec.to_string())
+            .or_default()
+            .insert(content, fetched_at);
+    }
 }
 
 impl Config {
@@ -50,6 +143,54 @@ impl Config {
         Ok(config)
     }
 
+    /// Write the configuration to a JSON file, replacing it atomically
+    pub fn save_to_file(&self, path: impl AsRef<std::path::Path>) -> anyhow::Result<()> {
+        let contents = serde_json::to_string_pretty(self)?;
+        write_atomically(path.as_ref(), contents)
+    }
+
+    /// Take `last_fetched` from the config saved at `path` for every character
+    /// that has none, matching characters by name and class
+    /// Nothing changes if no config was saved there yet
+    pub fn restore_fetch_times(&mut self, path: impl AsRef<std::path::Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(());
+        }
+
+        let saved = Self::from_file(path)?;
+        for character in self.characters.iter_mut().filter(|character| character.last_fetched.is_empty()) {
+            if let Some(previous) = saved
+                .characters
+                .iter()
+                .find(|previous| previous.name == character.name && previous.class == character.class)
+            {
+                character.last_fetched = previous.last_fetched.clone();
+            }
+        }
+        Ok(())
+    }
+
+    /// How recently a spec must have been fetched to be skipped, if incremental refresh is on
+    pub fn refresh_interval(&self) -> Option<chrono::Duration> {
+        self.refresh_interval_hours
+            .map(|hours| {
+                i64::try_from(hours)
+                    .ok()
+                    .and_then(chrono::Duration::try_hours)
+                    .unwrap_or(chrono::Duration::MAX)
+            })
+    }
+
+    /// Override URL configured for the `content` overview build of `spec`, if any
+    pub fn url_override(&self, spec: Spec, content: ContentType) -> Option<&str> {
+        self.spec_url_overrides
+            .iter()
+            .find(|(key, _)| key.parse::<Spec>().is_ok_and(|key| key == spec))
+            .and_then(|(_, urls)| urls.get(&content))
+            .map(String::as_str)
+    }
+
     /// Validate configuration settings
     fn validate(&self) -> anyhow::Result<()> {
         if self.characters.is_empty() {
@@ -60,6 +201,22 @@ impl Config {
             anyhow::bail!("Configuration must include at least one of: raid difficulties/bosses or dungeons");
         }
 
+        if let Some(min_popularity) = self.min_popularity {
+            if !(0.0..=100.0).contains(&min_popularity) {
+                anyhow::bail!("Minimum popularity must be between 0 and 100, got {}", min_popularity);
+            }
+        }
+
+        for (spec, urls) in &self.spec_url_overrides {
+            spec.parse::<Spec>()
+                .map_err(|e| anyhow::anyhow!("Invalid spec in URL overrides: {}", e))?;
+            for (content, url) in urls {
+                if url.trim().is_empty() {
+                    anyhow::bail!("Override URL for {} {} is empty", spec, content.as_path());
+                }
+            }
+        }
+
         for character in &self.characters {
             if character.class.is_empty() {
                 anyhow::bail!("Character '{}' has no class specified", character.name);
@@ -82,11 +239,13 @@ impl Config {
                     name: "MyWarrior".to_string(),
                     class: "Warrior".to_string(),
                     specializations: vec!["arms".to_string(), "fury".to_string()],
+                    last_fetched: BTreeMap::new(),
                 },
                 Character {
                     name: "MyMage".to_string(),
                     class: "Mage".to_string(),
                     specializations: vec!["frost".to_string(), "fire".to_string()],
+                    last_fetched: BTreeMap::new(),
                 },
             ],
             raid_difficulties: vec!["heroic".to_string(), "normal".to_string()],
@@ -102,6 +261,11 @@ impl Config {
             ],
             clear_previous_builds: false,
             output_path: PathBuf::from("/Applications/World of Warcraft/_retail_/WTF/Account/YOUR_ACCOUNT_ID/SavedVariables/TalentLoadoutsEx.lua"),
+            backup_count: default_backup_count(),
+            min_popularity: None,
+            refresh_interval_hours: None,
+            spec_url_overrides: BTreeMap::new(),
+            content_preference: Vec::new(),
         }
     }
 }
@@ -136,4 +300,64 @@ mod tests {
         config.characters[0].specializations.clear();
         assert!(config.validate().is_err());
     }
+
+    #[test]
+    fn test_url_overrides_are_looked_up_by_spec() {
+        let mut config = Config::example();
+        let raid_only = |url: &str| BTreeMap::from([(ContentType::Raid, url.to_string())]);
+        config
+            .spec_url_overrides
+            .insert("mage/fire".to_string(), raid_only("https://archon.gg/curated/fire"));
+        assert!(config.validate().is_ok());
+
+        assert_eq!(
+            config.url_override(Spec::FireMage, ContentType::Raid),
+            Some("https://archon.gg/curated/fire")
+        );
+        assert_eq!(config.url_override(Spec::FireMage, ContentType::MythicPlus), None);
+        assert_eq!(config.url_override(Spec::FrostMage, ContentType::Raid), None);
+
+        config.spec_url_overrides.insert("mage/ice".to_string(), raid_only("https://archon.gg/ice"));
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_fetched_within_interval() {
+        let now = Utc::now();
+        let mut character = Config::example().characters.remove(0);
+        character.mark_fetched("arms", ContentType::Raid, now - chrono::Duration::hours(2));
+
+        let interval = chrono::Duration::hours(6);
+        assert!(character.is_fetched_within("arms", ContentType::Raid, interval, now));
+        assert!(!character.is_fetched_within("arms", ContentType::Raid, chrono::Duration::hours(1), now));
+        assert!(!character.is_fetched_within("arms", ContentType::MythicPlus, interval, now));
+        assert!(!character.is_fetched_within("fury", ContentType::Raid, interval, now));
+    }
+
+    #[test]
+    fn test_spec_keyed_fetch_times_migrate_to_raid() {
+        let character: Character = serde_json::from_str(
+            r#"{
+                "name": "MyMage",
+                "class": "Mage",
+                "specializations": ["frost", "fire"],
+                "lastFetched": {
+                    "frost": "2025-03-10T12:00:00Z",
+                    "fire": { "raid": "2025-03-09T12:00:00Z", "mythic-plus": "2025-03-11T12:00:00Z" }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let at = |timestamp: &str| timestamp.parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(
+            character.last_fetched["frost"],
+            FetchTimes::from([(ContentType::Raid, at("2025-03-10T12:00:00Z"))])
+        );
+        assert_eq!(character.last_fetched["fire"][&ContentType::MythicPlus], at("2025-03-11T12:00:00Z"));
+
+        // Written back keyed by content type
+        let saved = serde_json::to_value(&character).unwrap();
+        assert_eq!(saved["lastFetched"]["frost"]["raid"], "2025-03-10T12:00:00Z");
+    }
 }
@@ -1,18 +1,163 @@
 // Modules
+mod addon_export;
 mod archon;
+mod atomic_file;
+mod backup;
+mod batch;
+mod body_cache;
+mod build_date;
+mod build_manifest;
+mod cache;
 mod config;
 mod fetcher;
+mod logging;
+mod lru_cache;
 mod lua_talent;
+mod metrics;
 mod orchestrator;
+mod popularity;
+mod rate_limit;
+mod settings;
+mod talent_codec;
+mod talent_validation;
+#[cfg(test)]
+mod test_support;
+mod url_import;
 mod warcraft_logs;
 mod wow;
 mod wow_scanner;
 
+use archon::ContentType;
+use batch::BatchSummary;
+use cache::DiskCache;
 use config::Config;
-use orchestrator::{TalentOrchestrator, UpdateSummary};
+use fetcher::{
+    ArchonFetcher, FailFast, FetchCancellation, FetchOutcome, FetchProgress, SharedFetcher, TalentBuild, UrlDiagnostics,
+};
+use futures_util::StreamExt;
+use metrics::FetchMetricsSnapshot;
+use talent_codec::LoadoutDiff;
+use talent_validation::{SpecKey, ValidationResult};
+use tauri::{Emitter, Manager, State};
+use tauri_plugin_opener::OpenerExt;
+use tauri_plugin_store::StoreExt;
+use orchestrator::{BuildDiff, BuildProgress, TalentOrchestrator, UpdateSummary};
+use settings::FetcherSettings;
+use std::collections::HashMap;
 use warcraft_logs::{DiscoveredContent, WarcraftLogsService};
+use wow::{Spec, WowClass};
 use wow_scanner::{DiscoveredCharacter, WowScanner};
 
+/// Event emitted to the window after each build of an update is fetched
+const FETCH_PROGRESS_EVENT: &str = "fetch-progress";
+
+/// Event emitted to the window after each fetch of a batch command resolves
+const BATCH_PROGRESS_EVENT: &str = "batch-progress";
+
+/// Event emitted to the window with each build of `stream_builds` as it arrives
+const BUILD_FETCHED_EVENT: &str = "build-fetched";
+
+/// Talent cache file name inside the app data directory
+const CACHE_FILE: &str = "talent_cache.json";
+
+/// Raw page bodies kept when `keep_response_bodies` is on, inside the app data directory
+const BODY_CACHE_DIR: &str = "response_bodies";
+
+/// Failed URLs of the last batch, inside the app data directory
+const FAILURES_FILE: &str = "failures.json";
+
+/// Config of the last update from the UI, inside the app data directory
+/// Keeps when each spec was fetched, which the UI's config doesn't carry
+const LAST_UPDATE_FILE: &str = "last_update.json";
+
+/// Fetcher settings file name inside the app data directory
+const FETCHER_SETTINGS_FILE: &str = "fetcher_settings.json";
+
+/// Store holding settings that outlive a session
+const SETTINGS_STORE: &str = "settings.json";
+
+/// Setting for where talents are written, overriding the config's `outputPath`
+const OUTPUT_PATH_SETTING: &str = "outputPath";
+
+/// How long closing the app waits for running updates and fetches to finish
+const SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Held by each update while it may write the talent file, so updates run one
+/// at a time instead of overwriting each other's changes
+#[derive(Default)]
+struct RunningUpdates(tokio::sync::Mutex<()>);
+
+/// On exit, give running updates and in-flight fetches up to `SHUTDOWN_TIMEOUT`
+/// to finish so the talent file isn't left behind half-updated
+async fn shutdown(app: &tauri::AppHandle) {
+    let deadline = tokio::time::Instant::now() + SHUTDOWN_TIMEOUT;
+
+    let updates = app.state::<RunningUpdates>();
+    if tokio::time::timeout_at(deadline, updates.0.lock()).await.is_err() {
+        tracing::warn!("Exiting while a talent update is still running");
+    }
+
+    let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+    app.state::<SharedFetcher>().drain(remaining).await;
+}
+
+fn app_data_dir(app: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))
+}
+
+/// Create a fetcher backed by the talent cache in the app data directory
+/// Built at startup and whenever the settings change, and shared by all
+/// commands through managed state
+fn create_fetcher(app: &tauri::AppHandle, settings: &FetcherSettings) -> Result<ArchonFetcher, String> {
+    let data_dir = app_data_dir(app)?;
+
+    let builder = settings
+        .apply(ArchonFetcher::builder())
+        .cache(DiskCache::open(data_dir.join(CACHE_FILE)));
+    let builder = if settings.keep_response_bodies {
+        builder.body_cache_dir(data_dir.join(BODY_CACHE_DIR))
+    } else {
+        builder
+    };
+    builder
+        .build()
+        .map_err(|e| format!("Failed to create fetcher: {}", e))
+}
+
+/// Saved fetcher settings, or the defaults if there are none yet
+fn load_fetcher_settings(app: &tauri::AppHandle) -> Result<FetcherSettings, String> {
+    FetcherSettings::load(&app_data_dir(app)?.join(FETCHER_SETTINGS_FILE))
+        .map_err(|e| format!("Failed to load fetcher settings: {}", e))
+}
+
+/// Tauri command to get the fetcher settings
+#[tauri::command]
+fn get_fetcher_settings(app: tauri::AppHandle) -> Result<FetcherSettings, String> {
+    load_fetcher_settings(&app)
+}
+
+/// Tauri command to save the fetcher settings and rebuild the shared fetcher with them
+/// Batches already running finish with the old settings
+#[tauri::command]
+fn update_fetcher_settings(
+    app: tauri::AppHandle,
+    fetcher: State<'_, SharedFetcher>,
+    settings: FetcherSettings,
+) -> Result<(), String> {
+    settings.validate().map_err(|e| format!("Invalid fetcher settings: {}", e))?;
+
+    let rebuilt = create_fetcher(&app, &settings)?;
+    rebuilt.set_offline(fetcher.get().is_offline());
+
+    settings
+        .save(&app_data_dir(&app)?.join(FETCHER_SETTINGS_FILE))
+        .map_err(|e| format!("Failed to save fetcher settings: {}", e))?;
+    fetcher.replace(rebuilt);
+    Ok(())
+}
+
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
 fn greet(name: &str) -> String {
@@ -34,6 +179,13 @@ fn find_wow_path() -> Result<String, String> {
         .ok_or_else(|| "Could not find WoW installation".to_string())
 }
 
+/// Tauri command to locate the TalentLoadoutsEx.lua file of the default WoW installation
+/// Meant for first run; `None` when nothing is found
+#[tauri::command]
+fn detect_talent_config_path() -> Option<String> {
+    WowScanner::find_talent_loadouts_path().map(|path| path.to_string_lossy().into_owned())
+}
+
 /// Tauri command to scan for characters in WoW installation
 #[tauri::command]
 fn scan_characters(wow_path: String) -> Result<Vec<DiscoveredCharacter>, String> {
@@ -43,26 +195,164 @@ fn scan_characters(wow_path: String) -> Result<Vec<DiscoveredCharacter>, String>
         .map_err(|e| format!("Failed to scan characters: {}", e))
 }
 
+/// Forward orchestrator progress to the window as `fetch-progress` events
+/// A failed emit only loses that update, it never stops the run
+fn emit_progress(window: tauri::Window) -> impl Fn(BuildProgress) + Send + Sync + 'static {
+    move |progress| {
+        if let Err(e) = window.emit(FETCH_PROGRESS_EVENT, &progress) {
+            tracing::warn!(event = FETCH_PROGRESS_EVENT, "Failed to emit event: {}", e);
+        }
+    }
+}
+
+/// Channel whose batch progress is forwarded to the window as `batch-progress` events
+/// Room for one update per URL, so none are dropped
+fn emit_batch_progress(window: tauri::Window, urls: usize) -> tokio::sync::mpsc::Sender<FetchProgress> {
+    let (sender, mut receiver) = tokio::sync::mpsc::channel(urls.max(1));
+    tauri::async_runtime::spawn(async move {
+        while let Some(progress) = receiver.recv().await {
+            if let Err(e) = window.emit(BATCH_PROGRESS_EVENT, &progress) {
+                tracing::warn!(event = BATCH_PROGRESS_EVENT, "Failed to emit event: {}", e);
+            }
+        }
+    });
+    sender
+}
+
+/// Output path saved with `set_output_path`, if any
+fn stored_output_path(app: &tauri::AppHandle) -> Result<Option<std::path::PathBuf>, String> {
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings: {}", e))?;
+    Ok(store
+        .get(OUTPUT_PATH_SETTING)
+        .and_then(|value| value.as_str().map(std::path::PathBuf::from)))
+}
+
+/// Write talents to the saved output path instead of the config's, if one is set
+fn apply_output_path(
+    app: &tauri::AppHandle,
+    orchestrator: TalentOrchestrator,
+) -> Result<TalentOrchestrator, String> {
+    Ok(match stored_output_path(app)? {
+        Some(path) => orchestrator.with_output_path(path),
+        None => orchestrator,
+    })
+}
+
+/// Tauri command to get the saved output path
+#[tauri::command]
+fn get_output_path(app: tauri::AppHandle) -> Result<Option<String>, String> {
+    Ok(stored_output_path(&app)?.map(|path| path.to_string_lossy().into_owned()))
+}
+
+/// Tauri command to save where updates write talents
+/// The directory must exist and be writable
+#[tauri::command]
+fn set_output_path(app: tauri::AppHandle, path: String) -> Result<(), String> {
+    atomic_file::ensure_writable(std::path::Path::new(&path))
+        .map_err(|e| format!("Failed to set output path: {}", e))?;
+
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings: {}", e))?;
+    store.set(OUTPUT_PATH_SETTING, path);
+    store.save().map_err(|e| format!("Failed to save settings: {}", e))
+}
+
+/// Orchestrator for an update from the UI, skipping and recording fetch times
+/// through the config saved at `last_update_path`
+fn orchestrator_with_fetch_times(
+    mut config: Config,
+    fetcher: ArchonFetcher,
+    last_update_path: &std::path::Path,
+) -> Result<TalentOrchestrator, String> {
+    config
+        .restore_fetch_times(last_update_path)
+        .map_err(|e| format!("Failed to load fetch times: {}", e))?;
+    Ok(TalentOrchestrator::with_fetcher(config, fetcher).with_config_path(last_update_path))
+}
+
 /// Tauri command to update talents from Archon.gg
 #[tauri::command]
-async fn update_talents_from_config(config: Config) -> Result<UpdateSummary, String> {
+async fn update_talents_from_config(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    fetcher: State<'_, SharedFetcher>,
+    cancellation: State<'_, FetchCancellation>,
+    updates: State<'_, RunningUpdates>,
+    config: Config,
+) -> Result<UpdateSummary, String> {
+    let _running = updates.0.lock().await;
+
     // Create orchestrator and run
-    let orchestrator = TalentOrchestrator::new(config);
-    orchestrator
+    let last_update_path = app_data_dir(&app)?.join(LAST_UPDATE_FILE);
+    let orchestrator = orchestrator_with_fetch_times(config, fetcher.get(), &last_update_path)?
+        .with_progress(emit_progress(window))
+        .with_cancellation(cancellation.start());
+    apply_output_path(&app, orchestrator)?
         .run()
         .await
         .map_err(|e| format!("Failed to update talents: {}", e))
 }
 
+/// Tauri command to fetch builds only for the specs the talent file has none for yet
+#[tauri::command]
+async fn fill_missing_builds(
+    app: tauri::AppHandle,
+    fetcher: State<'_, SharedFetcher>,
+    updates: State<'_, RunningUpdates>,
+    config: Config,
+) -> Result<UpdateSummary, String> {
+    let _running = updates.0.lock().await;
+
+    let orchestrator = TalentOrchestrator::with_fetcher(config, fetcher.get());
+    apply_output_path(&app, orchestrator)?
+        .fill_missing()
+        .await
+        .map_err(|e| format!("Failed to fetch missing builds: {}", e))
+}
+
+/// Tauri command to preview how an update would change the auto-generated talents
+/// Nothing is written
+#[tauri::command]
+async fn preview_talent_update(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    fetcher: State<'_, SharedFetcher>,
+    cancellation: State<'_, FetchCancellation>,
+    config: Config,
+) -> Result<Vec<BuildDiff>, String> {
+    let orchestrator = TalentOrchestrator::with_fetcher(config, fetcher.get())
+        .with_progress(emit_progress(window))
+        .with_cancellation(cancellation.start());
+    apply_output_path(&app, orchestrator)?
+        .dry_run()
+        .await
+        .map_err(|e| format!("Failed to preview talent update: {}", e))
+}
+
 /// Tauri command to update talents from a config file (kept for backwards compatibility)
 #[tauri::command]
-async fn update_talents(config_path: String) -> Result<String, String> {
+async fn update_talents(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    fetcher: State<'_, SharedFetcher>,
+    cancellation: State<'_, FetchCancellation>,
+    updates: State<'_, RunningUpdates>,
+    config_path: String,
+) -> Result<String, String> {
+    let _running = updates.0.lock().await;
+
     // Load configuration
     let config = Config::from_file(&config_path).map_err(|e| format!("Failed to load config: {}", e))?;
 
     // Create orchestrator and run
-    let orchestrator = TalentOrchestrator::new(config);
-    orchestrator
+    let orchestrator = TalentOrchestrator::with_fetcher(config, fetcher.get())
+        .with_progress(emit_progress(window))
+        .with_cancellation(cancellation.start())
+        .with_config_path(&config_path);
+    apply_output_path(&app, orchestrator)?
         .run()
         .await
         .map_err(|e| format!("Failed to update talents: {}", e))?;
@@ -70,6 +360,253 @@ async fn update_talents(config_path: String) -> Result<String, String> {
     Ok("Talents updated successfully!".to_string())
 }
 
+/// How a batch command fetches its URLs
+#[derive(Debug, Clone, Copy)]
+enum BatchMode {
+    /// Fetch every URL, reporting progress as each fetch resolves
+    All,
+    /// Fail at the first failed fetch
+    FailFast(FailFast),
+    /// Give up on the fetches still running after this long
+    Deadline(std::time::Duration),
+}
+
+impl BatchMode {
+    fn new(fail_fast: Option<FailFast>, deadline_secs: Option<u64>) -> Result<Self, String> {
+        match (fail_fast, deadline_secs) {
+            (Some(_), Some(_)) => Err("A batch can't both fail fast and have a deadline".to_string()),
+            (Some(fail_fast), None) => Ok(Self::FailFast(fail_fast)),
+            (None, Some(secs)) => Ok(Self::Deadline(std::time::Duration::from_secs(secs))),
+            (None, None) => Ok(Self::All),
+        }
+    }
+}
+
+/// Fetch a batch of build pages and record the failed ones in the failures file
+/// Only `BatchMode::All` reports progress to `window`; fetches a deadline cuts
+/// off are recorded as failures
+async fn fetch_batch_recording_failures(
+    app: &tauri::AppHandle,
+    window: tauri::Window,
+    fetcher: &ArchonFetcher,
+    urls: &[String],
+    mode: BatchMode,
+) -> Result<BatchSummary, String> {
+    let summary = match mode {
+        BatchMode::All => BatchSummary::from_results(
+            fetcher
+                .fetch_many_with_progress(urls, emit_batch_progress(window, urls.len()))
+                .await,
+        ),
+        BatchMode::FailFast(fail_fast) => BatchSummary::from_results(
+            fetcher
+                .fetch_many_fail_fast(urls, fail_fast)
+                .await
+                .map_err(|e| format!("Failed to fetch builds: {:#}", e))?
+                .into_iter()
+                .map(|(url, outcome)| (url, Ok(outcome)))
+                .collect(),
+        ),
+        BatchMode::Deadline(overall) => {
+            BatchSummary::from_deadline_batch(fetcher.fetch_many_within(urls, overall).await)
+        }
+    };
+
+    summary
+        .save_failures(&app_data_dir(app)?.join(FAILURES_FILE))
+        .map_err(|e| format!("Failed to save failed fetches: {}", e))?;
+
+    Ok(summary)
+}
+
+/// Tauri command to fetch several build pages at once
+/// With `deadline_secs`, fetches still running after that long are given up on
+#[tauri::command]
+async fn fetch_builds(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    fetcher: State<'_, SharedFetcher>,
+    urls: Vec<String>,
+    fail_fast: Option<FailFast>,
+    deadline_secs: Option<u64>,
+) -> Result<BatchSummary, String> {
+    let mode = BatchMode::new(fail_fast, deadline_secs)?;
+    fetch_batch_recording_failures(&app, window, &fetcher.get(), &urls, mode).await
+}
+
+/// Tauri command to fetch every build URL listed in a CSV or text file
+#[tauri::command]
+async fn fetch_builds_from_file(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    fetcher: State<'_, SharedFetcher>,
+    path: String,
+    fail_fast: Option<FailFast>,
+    deadline_secs: Option<u64>,
+) -> Result<BatchSummary, String> {
+    let mode = BatchMode::new(fail_fast, deadline_secs)?;
+    let requests = url_import::load_urls_from_file(&path).map_err(|e| format!("Failed to load URL file: {:#}", e))?;
+    let urls: Vec<String> = requests.into_iter().map(|request| request.url).collect();
+
+    fetch_batch_recording_failures(&app, window, &fetcher.get(), &urls, mode).await
+}
+
+/// Tauri command to fetch the overview build of every spec for a content type
+#[tauri::command]
+async fn refresh_all_builds(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    fetcher: State<'_, SharedFetcher>,
+    content: String,
+) -> Result<BatchSummary, String> {
+    let content = ContentType::from_str(&content)
+        .ok_or_else(|| format!("Unknown content type: {}", content))?;
+    let urls: Vec<String> = archon::all_default_build_urls(content)
+        .into_iter()
+        .map(|(_, url)| url)
+        .collect();
+
+    fetch_batch_recording_failures(&app, window, &fetcher.get(), &urls, BatchMode::All).await
+}
+
+/// Tauri command to re-fetch only the URLs that failed in the last batch
+#[tauri::command]
+async fn retry_failed_fetches(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    fetcher: State<'_, SharedFetcher>,
+) -> Result<BatchSummary, String> {
+    let failures = batch::load_failures(&app_data_dir(&app)?.join(FAILURES_FILE))
+        .map_err(|e| format!("Failed to load failed fetches: {}", e))?;
+    let urls: Vec<String> = failures.into_iter().map(|failure| failure.url).collect();
+
+    fetch_batch_recording_failures(&app, window, &fetcher.get(), &urls, BatchMode::All).await
+}
+
+/// A build sent with a `build-fetched` event
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StreamedBuild {
+    url: String,
+    talent_string: Option<String>,
+    /// Why the fetch failed, if it did
+    error: Option<String>,
+}
+
+/// Tauri command to fetch several build pages, emitting each build to the window
+/// as soon as it is fetched instead of waiting for the whole batch
+/// Returns how many builds were found
+#[tauri::command]
+async fn stream_builds(
+    window: tauri::Window,
+    fetcher: State<'_, SharedFetcher>,
+    urls: Vec<String>,
+) -> Result<usize, String> {
+    let fetcher = fetcher.get();
+    let mut builds = std::pin::pin!(fetcher.fetch_stream(&urls));
+
+    let mut found = 0;
+    while let Some((url, result)) = builds.next().await {
+        let build = match result {
+            Ok(talent_string) => {
+                found += usize::from(talent_string.is_some());
+                StreamedBuild { url, talent_string, error: None }
+            }
+            Err(e) => StreamedBuild {
+                url,
+                talent_string: None,
+                error: Some(format!("{:#}", e)),
+            },
+        };
+        if let Err(e) = window.emit(BUILD_FETCHED_EVENT, &build) {
+            tracing::warn!(event = BUILD_FETCHED_EVENT, "Failed to emit event: {}", e);
+        }
+    }
+
+    Ok(found)
+}
+
+/// Tauri command to cancel running talent updates
+/// Builds fetched before the cancellation are still written
+#[tauri::command]
+fn cancel_fetches(cancellation: State<'_, FetchCancellation>) {
+    cancellation.cancel();
+}
+
+/// Tauri command to check whether Archon.gg can be fetched from right now
+#[tauri::command]
+async fn check_archon_availability(fetcher: State<'_, SharedFetcher>) -> Result<bool, String> {
+    fetcher
+        .get()
+        .check_availability()
+        .await
+        .map_err(|e| format!("Failed to check Archon availability: {}", e))
+}
+
+/// Tauri command to open a connection to Archon ahead of the first fetch
+#[tauri::command]
+async fn warm_up_fetcher(fetcher: State<'_, SharedFetcher>) -> Result<(), String> {
+    fetcher
+        .get()
+        .warm_up()
+        .await
+        .map_err(|e| format!("Failed to warm up connection: {}", e))
+}
+
+/// Tauri command to report how the shared fetcher's requests have gone so far
+#[tauri::command]
+fn fetch_metrics(fetcher: State<'_, SharedFetcher>) -> FetchMetricsSnapshot {
+    fetcher.get().metrics()
+}
+
+/// Tauri command to delete every cached build, returning how many were removed
+#[tauri::command]
+fn clear_cache(fetcher: State<'_, SharedFetcher>) -> Result<usize, String> {
+    fetcher.get().clear_cache().map_err(|e| format!("Failed to clear cache: {}", e))
+}
+
+/// Tauri command to restore the talent file from its most recent backup
+/// Returns the path of the backup that was restored
+#[tauri::command]
+fn restore_talent_backup(output_path: String) -> Result<String, String> {
+    backup::restore_latest_backup(std::path::Path::new(&output_path))
+        .map(|backup_path| backup_path.to_string_lossy().to_string())
+        .map_err(|e| format!("Failed to restore backup: {}", e))
+}
+
+/// Tauri command to save builds to a manifest file that others can import
+#[tauri::command]
+fn export_builds(builds: Vec<TalentBuild>, path: String) -> Result<(), String> {
+    build_manifest::export_builds(&builds, std::path::Path::new(&path))
+        .map_err(|e| format!("Failed to export builds: {}", e))
+}
+
+/// Tauri command to read builds from a manifest file made by `export_builds`
+#[tauri::command]
+fn import_builds(path: String) -> Result<Vec<TalentBuild>, String> {
+    build_manifest::import_builds(std::path::Path::new(&path)).map_err(|e| format!("Failed to import builds: {}", e))
+}
+
+/// Tauri command to wrap a build into an addon import string
+#[tauri::command]
+fn addon_export_string(build: TalentBuild) -> Result<String, String> {
+    addon_export::to_addon_string(&build).map_err(|e| format!("Failed to create addon string: {}", e))
+}
+
+/// Tauri command to list the specs a talent file has no Raid or Mythic+ builds for yet
+#[tauri::command]
+fn find_missing_specs(path: String) -> Result<Vec<SpecKey>, String> {
+    let manager = lua_talent::LuaTalentManager::load_from_file(&path)
+        .map_err(|e| format!("Failed to load talent file: {}", e))?;
+    Ok(talent_validation::missing_specs(&manager, &orchestrator::FETCHED_CONTENT))
+}
+
+/// Tauri command to check every talent string in a talent file
+#[tauri::command]
+fn validate_talent_file(path: String) -> Result<Vec<(SpecKey, ValidationResult)>, String> {
+    talent_validation::validate_config(&path).map_err(|e| format!("Failed to validate talent file: {}", e))
+}
+
 /// Tauri command to auto-discover current raids and dungeons from Warcraft Logs
 #[tauri::command]
 async fn discover_content() -> Result<DiscoveredContent, String> {
@@ -78,21 +615,521 @@ async fn discover_content() -> Result<DiscoveredContent, String> {
         .map_err(|e| format!("Failed to discover content: {}", e))
 }
 
+/// Fetch a single build page, turning failed fetches into an error message
+/// Pages without a build (no data yet, no talent link) are `None`
+async fn fetch_build(fetcher: &ArchonFetcher, url: &str) -> Result<Option<String>, String> {
+    let outcome = fetcher
+        .fetch_talent_build(url)
+        .await
+        .map_err(|e| format!("Failed to fetch build: {}", e))?;
+
+    talent_string_or_error(outcome, url)
+}
+
+/// Fetch a single build page with its name, popularity and last-updated date,
+/// as far as the page shows them
+/// Builds served from the cache only carry their code and popularity
+async fn preview(fetcher: &ArchonFetcher, url: &str) -> Result<Option<TalentBuild>, String> {
+    let page = fetcher
+        .fetch_page(url)
+        .await
+        .map_err(|e| format!("Failed to fetch build: {}", e))?;
+
+    let Some(talent_string) = talent_string_or_error(page.outcome, url)? else {
+        return Ok(None);
+    };
+    Ok(Some(page.build.unwrap_or_else(|| TalentBuild {
+        popularity: page.popularity,
+        ..TalentBuild::from_talent_string(&talent_string)
+    })))
+}
+
+/// The talent string of a found build; `None` for pages without a build
+/// (no data yet, no talent link), and an error message for failed fetches
+fn talent_string_or_error(outcome: FetchOutcome, url: &str) -> Result<Option<String>, String> {
+    match outcome {
+        FetchOutcome::Found(talent_string) => Ok(Some(talent_string)),
+        FetchOutcome::NoData | FetchOutcome::NoTalentLink => Ok(None),
+        outcome @ FetchOutcome::HttpError(_) => Err(format!(
+            "HTTP {} for {}",
+            outcome.http_error_message().unwrap_or_default(),
+            url
+        )),
+        FetchOutcome::Network(e) => Err(format!("Failed to fetch {}: {}", url, e)),
+        FetchOutcome::Blocked => Err(format!(
+            "Blocked by a Cloudflare challenge for {}; open the page in a browser once and retry",
+            url
+        )),
+        FetchOutcome::OfflineMiss => Err(format!("{} is not cached and offline mode is on", url)),
+        FetchOutcome::BudgetExceeded => Err(format!("Download budget used up before {} was fetched", url)),
+        FetchOutcome::UnexpectedContentType(content_type) => {
+            Err(format!("{} returned {} instead of an HTML page", url, content_type))
+        }
+        FetchOutcome::UnreadableBody(reason) => Err(format!("Could not read the response from {}: {}", url, reason)),
+        FetchOutcome::SpecMismatch { requested, got } => Err(format!(
+            "{} has a {} build instead of {}",
+            url,
+            got.as_wowhead_path(),
+            requested.as_wowhead_path()
+        )),
+    }
+}
+
+/// Diff `my_code` (an in-game export string) against Archon's build for `spec`
+/// A code for another spec is reported as `LoadoutDiff::SpecChanged` without fetching
+async fn compare_with_archon(
+    fetcher: &ArchonFetcher,
+    my_code: &str,
+    spec: Spec,
+    content: ContentType,
+) -> Result<LoadoutDiff, String> {
+    // Accept wowhead's "class/spec/CODE" form as well
+    let mine = talent_codec::decode(my_code.trim().rsplit('/').next().unwrap_or_default())
+        .map_err(|e| format!("Failed to decode your talent code: {}", e))?;
+    if mine.spec_id != spec.blizzard_id() {
+        return Ok(LoadoutDiff::SpecChanged {
+            old_spec_id: mine.spec_id,
+            new_spec_id: spec.blizzard_id(),
+        });
+    }
+
+    let url = fetcher.overview_url(spec, content);
+    let talent_string = fetch_build(fetcher, &url)
+        .await?
+        .ok_or_else(|| format!("Archon has no {} build for {} yet", content.as_path(), spec.as_wowhead_path()))?;
+    let archon = talent_codec::decode(talent_string.rsplit('/').next().unwrap_or_default())
+        .map_err(|e| format!("Failed to decode Archon's talent code: {}", e))?;
+
+    Ok(talent_codec::diff_loadouts(&mine, &archon))
+}
+
+/// Tauri command to compare an in-game talent code to Archon's recommended build
+#[tauri::command]
+async fn compare_to_archon(
+    fetcher: State<'_, SharedFetcher>,
+    code: String,
+    class: String,
+    spec: String,
+    content: String,
+) -> Result<LoadoutDiff, String> {
+    let wow_class = WowClass::from_name(&class).ok_or_else(|| format!("Unknown class: {}", class))?;
+    let spec = Spec::from_class_and_name(wow_class, &spec)
+        .ok_or_else(|| format!("Unknown spec '{}' for class {}", spec, wow_class.to_url_format()))?;
+    let content = ContentType::from_str(&content)
+        .ok_or_else(|| format!("Unknown content type: {}", content))?;
+
+    compare_with_archon(&fetcher.get(), &code, spec, content).await
+}
+
+/// Tauri command to switch offline mode, where builds come only from the cache
+#[tauri::command]
+fn set_offline_mode(fetcher: State<'_, SharedFetcher>, offline: bool) {
+    fetcher.get().set_offline(offline);
+}
+
+/// Tauri command to fetch the talent string of a single Archon.gg build page,
+/// optionally giving each attempt `timeout_secs` instead of the fetcher's timeout
+#[tauri::command]
+async fn fetch_talent_build_cmd(
+    fetcher: State<'_, SharedFetcher>,
+    url: String,
+    timeout_secs: Option<u64>,
+) -> Result<Option<String>, String> {
+    let Some(timeout_secs) = timeout_secs else {
+        return fetch_build(&fetcher.get(), &url).await;
+    };
+    let outcome = fetcher
+        .get()
+        .fetch_with_timeout(&url, std::time::Duration::from_secs(timeout_secs))
+        .await
+        .map_err(|e| format!("Failed to fetch build: {}", e))?;
+
+    talent_string_or_error(outcome, &url)
+}
+
+/// Tauri command to fetch the talent string of a pasted Archon.gg link
+/// Links to any other host are refused, even when the fetcher allows them
+#[tauri::command]
+async fn fetch_pasted_build(fetcher: State<'_, SharedFetcher>, url: String) -> Result<Option<String>, String> {
+    let parsed = fetcher::parse_archon_url(&url).map_err(|e| format!("Failed to fetch build: {}", e))?;
+    let outcome = fetcher
+        .get()
+        .fetch_url(&parsed)
+        .await
+        .map_err(|e| format!("Failed to fetch build: {}", e))?;
+
+    talent_string_or_error(outcome, &url)
+}
+
+/// Tauri command to fetch a single build page with extra request headers,
+/// which replace the default headers of the same name
+#[tauri::command]
+async fn fetch_build_with_headers(
+    fetcher: State<'_, SharedFetcher>,
+    url: String,
+    headers: HashMap<String, String>,
+) -> Result<Option<String>, String> {
+    let headers = fetcher::parse_headers(headers.iter().map(|(name, value)| (name.as_str(), value.as_str())))
+        .map_err(|e| format!("Invalid headers: {}", e))?;
+    let outcome = fetcher
+        .get()
+        .fetch_with_headers(&url, headers)
+        .await
+        .map_err(|e| format!("Failed to fetch build: {}", e))?;
+
+    talent_string_or_error(outcome, &url)
+}
+
+/// Tauri command to fetch a build page and return its build without applying it
+#[tauri::command]
+async fn preview_build(fetcher: State<'_, SharedFetcher>, url: String) -> Result<Option<TalentBuild>, String> {
+    preview(&fetcher.get(), &url).await
+}
+
+/// Tauri command to re-run the parser over the bodies kept for `urls`, keyed by URL
+/// Needs `keep_response_bodies`; nothing is fetched
+#[tauri::command]
+fn reparse_cached_builds(
+    fetcher: State<'_, SharedFetcher>,
+    urls: Vec<String>,
+) -> Result<HashMap<String, Option<String>>, String> {
+    let fetcher = fetcher.get();
+    urls.into_iter()
+        .map(|url| {
+            let talent_string = fetcher
+                .reparse_cached(&url)
+                .map_err(|e| format!("Failed to reparse {}: {:#}", url, e))?;
+            Ok((url, talent_string))
+        })
+        .collect()
+}
+
+/// Tauri command to fetch a URL once and report what the parser saw, for support requests
+#[tauri::command]
+async fn diagnose_url(fetcher: State<'_, SharedFetcher>, url: String) -> Result<UrlDiagnostics, String> {
+    fetcher
+        .get()
+        .diagnose(&url)
+        .await
+        .map_err(|e| format!("Failed to diagnose {}: {:#}", url, e))
+}
+
+/// Tauri command to list the talent string of every build on a page, in page order
+#[tauri::command]
+async fn fetch_page_builds(fetcher: State<'_, SharedFetcher>, url: String) -> Result<Vec<String>, String> {
+    fetcher
+        .get()
+        .fetch_all_talent_strings(&url)
+        .await
+        .map_err(|e| format!("Failed to fetch builds from {}: {:#}", url, e))
+}
+
+/// Tauri command to fetch the builds of every spec of a class, keyed by spec name
+#[tauri::command]
+async fn fetch_class_builds(
+    fetcher: State<'_, SharedFetcher>,
+    class: String,
+    content: String,
+) -> Result<HashMap<String, Option<String>>, String> {
+    let wow_class = WowClass::from_name(&class).ok_or_else(|| format!("Unknown class: {}", class))?;
+    let content = ContentType::from_str(&content)
+        .ok_or_else(|| format!("Unknown content type: {}", content))?;
+
+    let builds = fetcher.get().fetch_class_builds(wow_class, content).await;
+    Ok(builds
+        .into_iter()
+        .map(|(spec, talent_string)| (spec.name().to_string(), talent_string))
+        .collect())
+}
+
+/// Tauri command to fetch the builds of every class, keyed by class and then spec name
+#[tauri::command]
+async fn fetch_all_class_builds(
+    fetcher: State<'_, SharedFetcher>,
+    content: String,
+) -> Result<HashMap<String, HashMap<String, Option<String>>>, String> {
+    let content = ContentType::from_str(&content)
+        .ok_or_else(|| format!("Unknown content type: {}", content))?;
+
+    let builds = fetcher.get().fetch_builds_by_class(content).await;
+    Ok(builds
+        .into_iter()
+        .map(|(class, specs)| {
+            let specs = specs
+                .into_iter()
+                .map(|(spec, talent_string)| (spec.name().to_string(), talent_string))
+                .collect();
+            (class.to_url_format().to_string(), specs)
+        })
+        .collect())
+}
+
+/// Tauri command to fetch the talent string of a named build variant (e.g. "cleave")
+#[tauri::command]
+async fn fetch_build_variant(
+    fetcher: State<'_, SharedFetcher>,
+    class: String,
+    spec: String,
+    content: String,
+    variant: String,
+) -> Result<Option<String>, String> {
+    let wow_class = WowClass::from_name(&class).ok_or_else(|| format!("Unknown class: {}", class))?;
+    let spec = Spec::from_class_and_name(wow_class, &spec)
+        .ok_or_else(|| format!("Unknown spec '{}' for class {}", spec, wow_class.to_url_format()))?;
+    let content = ContentType::from_str(&content)
+        .ok_or_else(|| format!("Unknown content type: {}", content))?;
+
+    fetcher
+        .get()
+        .fetch_variant(spec, content, &variant)
+        .await
+        .map_err(|e| format!("Failed to fetch build variant: {:#}", e))
+}
+
+/// Tauri command to build an Archon.gg overview URL from class/spec/content names
+#[tauri::command]
+fn build_archon_url(class: String, spec: String, content: String) -> Result<String, String> {
+    let content = ContentType::from_str(&content)
+        .ok_or_else(|| format!("Unknown content type: {}", content))?;
+    archon::build_url(&class, &spec, content).map_err(|e| format!("Failed to build URL: {}", e))
+}
+
+/// Tauri command to open an Archon.gg build page in the default browser
+/// Links to any other host are refused
+#[tauri::command]
+fn open_build_page(app: tauri::AppHandle, url: String) -> Result<(), String> {
+    let url = fetcher::parse_archon_url(&url).map_err(|e| format!("Failed to open build page: {}", e))?;
+    app.opener()
+        .open_url(url.as_str(), None::<&str>)
+        .map_err(|e| format!("Failed to open build page: {}", e))
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    logging::init();
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_store::Builder::new().build())
+        .setup(|app| {
+            // A broken settings file shouldn't keep the app from starting
+            let settings = load_fetcher_settings(app.handle()).unwrap_or_else(|e| {
+                tracing::warn!("{}, using defaults", e);
+                FetcherSettings::default()
+            });
+            let fetcher = create_fetcher(app.handle(), &settings)?;
+            app.manage(SharedFetcher::new(fetcher));
+            app.manage(FetchCancellation::default());
+            app.manage(RunningUpdates::default());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             read_file,
             find_wow_path,
+            detect_talent_config_path,
             scan_characters,
             update_talents_from_config,
             update_talents,
-            discover_content
+            fill_missing_builds,
+            preview_talent_update,
+            discover_content,
+            build_archon_url,
+            open_build_page,
+            fetch_talent_build_cmd,
+            fetch_build_with_headers,
+            preview_build,
+            diagnose_url,
+            fetch_class_builds,
+            fetch_all_class_builds,
+            fetch_page_builds,
+            fetch_pasted_build,
+            fetch_build_variant,
+            reparse_cached_builds,
+            compare_to_archon,
+            cancel_fetches,
+            fetch_metrics,
+            clear_cache,
+            set_offline_mode,
+            get_fetcher_settings,
+            update_fetcher_settings,
+            check_archon_availability,
+            warm_up_fetcher,
+            fetch_builds,
+            fetch_builds_from_file,
+            stream_builds,
+            refresh_all_builds,
+            retry_failed_fetches,
+            restore_talent_backup,
+            get_output_path,
+            set_output_path,
+            export_builds,
+            import_builds,
+            addon_export_string,
+            validate_talent_file,
+            find_missing_specs
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                tauri::async_runtime::block_on(shutdown(app));
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+    use test_support::{fetcher_builder, MockResponse, MockServer};
+
+    #[tokio::test]
+    async fn test_fetch_build_maps_outcomes_for_the_frontend() {
+        let server = MockServer::start().await;
+        server.respond(
+            "/found",
+            MockResponse::html(r#"<a href="https://www.wowhead.com/talent-calc/blizzard/mage/frost/CcQAAAAAAAAAAAAAAAAAAAAAAAAABC123">Build</a>"#),
+        );
+        server.respond("/no-data", MockResponse::status(500));
+        server.respond("/missing", MockResponse::status(404));
+
+        let fetcher = fetcher_builder().max_retries(0).build().unwrap();
+
+        assert_eq!(
+            fetch_build(&fetcher, &server.url("/found")).await,
+            Ok(Some("mage/frost/CcQAAAAAAAAAAAAAAAAAAAAAAAAABC123".to_string()))
+        );
+        assert_eq!(fetch_build(&fetcher, &server.url("/no-data")).await, Ok(None));
+
+        let error = fetch_build(&fetcher, &server.url("/missing")).await.unwrap_err();
+        assert!(error.contains("404"), "unexpected error: {}", error);
+    }
+
+    #[tokio::test]
+    async fn test_preview_returns_the_page_metadata() {
+        use chrono::TimeZone;
+
+        let server = MockServer::start().await;
+        server.respond(
+            "/build",
+            MockResponse::html(
+                r#"
+                <h2>Raid — Single Target</h2>
+                <p class="build-meta">Last updated: March 3, 2025</p>
+                <div class="build-popularity"><span>62.5</span> %</div>
+                <a href="https://www.wowhead.com/talent-calc/blizzard/mage/frost/CcQAAAAAAAAAAAAAAAAAAAAAAAAABC123">Build</a>
+                "#,
+            ),
+        );
+        server.respond("/no-data", MockResponse::status(500));
+
+        let fetcher = fetcher_builder().max_retries(0).build().unwrap();
+
+        let build = preview(&fetcher, &server.url("/build")).await.unwrap().unwrap();
+        assert_eq!(build.name.as_deref(), Some("Raid — Single Target"));
+        assert_eq!(build.popularity, Some(62.5));
+        assert_eq!(build.last_updated, Some(chrono::Utc.with_ymd_and_hms(2025, 3, 3, 0, 0, 0).unwrap()));
+        assert_eq!((build.class.as_str(), build.spec.as_str()), ("mage", "frost"));
+        assert_eq!(build.encoded, "CcQAAAAAAAAAAAAAAAAAAAAAAAAABC123");
+
+        assert_eq!(preview(&fetcher, &server.url("/no-data")).await, Ok(None));
+    }
+
+    #[tokio::test]
+    async fn test_compare_with_archon_diffs_against_the_fetched_build() {
+        let server = MockServer::start().await;
+        // The hand-built frost mage code from the codec tests, with a second rank in node 2
+        server.respond(
+            "/frost/mage/raid/overview",
+            MockResponse::html(r#"<a href="https://www.wowhead.com/talent-calc/blizzard/mage/frost/CAEAAAAAAAAAAAAAAAAAAAAAAMugtA">Build</a>"#),
+        );
+        let fetcher = fetcher_builder().max_retries(0).base_url(server.url("")).build().unwrap();
+        let mine = "CAEAAAAAAAAAAAAAAAAAAAAAAMegtA";
+
+        let diff = compare_with_archon(&fetcher, mine, Spec::FrostMage, ContentType::Raid).await.unwrap();
+        let LoadoutDiff::Nodes { added, removed, changed } = diff else {
+            panic!("same spec, got {:?}", diff);
+        };
+        assert!(added.is_empty() && removed.is_empty());
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].id, 2);
+        assert_eq!((changed[0].old.rank, changed[0].new.rank), (Some(1), Some(2)));
+
+        // A frost code compared against fire needs no fetch
+        let diff = compare_with_archon(&fetcher, mine, Spec::FireMage, ContentType::Raid).await.unwrap();
+        assert_eq!(diff, LoadoutDiff::SpecChanged { old_spec_id: 64, new_spec_id: 63 });
+        assert_eq!(server.hits("/fire/mage/raid/overview"), 0);
+
+        let error = compare_with_archon(&fetcher, "not a code", Spec::FrostMage, ContentType::Raid)
+            .await
+            .unwrap_err();
+        assert!(error.contains("your talent code"), "unexpected error: {}", error);
+    }
+
+    #[tokio::test]
+    async fn test_commands_share_the_managed_fetchers_concurrency_limit() {
+        let server = MockServer::start().await;
+        let delay = Duration::from_millis(200);
+        server.respond("/a", MockResponse::html("<html></html>").delay(delay));
+        server.respond("/b", MockResponse::html("<html></html>").delay(delay));
+
+        // What managed state hands each command: clones of the one fetcher
+        let managed = fetcher_builder().concurrency(1).build().unwrap();
+        let (first, second) = (managed.clone(), managed.clone());
+
+        let (url_a, url_b) = (server.url("/a"), server.url("/b"));
+        let start = Instant::now();
+        let (a, b) = tokio::join!(fetch_build(&first, &url_a), fetch_build(&second, &url_b));
+        assert_eq!((a, b), (Ok(None), Ok(None)));
+
+        // With a shared single permit the second request can't start until the first is done
+        assert!(start.elapsed() >= delay * 2, "requests overlapped: {:?}", start.elapsed());
+    }
+
+    #[tokio::test]
+    async fn test_updates_from_the_ui_remember_fetch_times() {
+        const SIKRAN_PATH: &str = "/frost/mage/raid/overview/heroic/sikran";
+
+        let server = MockServer::start().await;
+        server.respond(
+            SIKRAN_PATH,
+            MockResponse::html(r#"<a href="https://www.wowhead.com/talent-calc/blizzard/mage/frost/CcQAAAAAAAAAAAAAAAAAAAAAAAAABC123">Build</a>"#),
+        );
+
+        let dir = test_support::TempDir::new();
+        let last_update_path = dir.path().join(LAST_UPDATE_FILE);
+        // Shaped like the config the UI sends, which has no fetch times
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "characters": [{ "name": "MyMage", "class": "Mage", "specializations": ["frost"] }],
+            "raidDifficulties": ["heroic"],
+            "raidBosses": ["sikran"],
+            "dungeons": [],
+            "clearPreviousBuilds": false,
+            "outputPath": dir.path().join("TalentLoadoutsEx.lua"),
+            "refreshIntervalHours": 24
+        }))
+        .unwrap();
+
+        let update = || async {
+            let fetcher = fetcher_builder().max_retries(0).build().unwrap();
+            orchestrator_with_fetch_times(config.clone(), fetcher, &last_update_path)
+                .unwrap()
+                .with_url_builder(archon::ArchonUrlBuilder::with_base_url(server.url("")))
+                .run()
+                .await
+                .unwrap()
+        };
+
+        let first = update().await;
+        assert_eq!(first.raid_talents, 1);
+        let saved = Config::from_file(&last_update_path).unwrap();
+        assert!(saved.characters[0].last_fetched["frost"].contains_key(&ContentType::Raid));
+
+        // The next update from the UI knows frost was just fetched
+        let second = update().await;
+        assert_eq!(second.specs_up_to_date, 1);
+        assert_eq!(server.hits(SIKRAN_PATH), 1);
+    }
 }
@@ -0,0 +1,303 @@
+//! Minimal `tracing` subscriber that prints events to stderr, filtered by `RUST_LOG`
+//!
+//! Supports the common `RUST_LOG` forms: a bare level (`debug`), per-target
+//! levels (`talent_heron_lib::fetcher=debug`) and comma-separated combinations
+//! of both. Targets match by module path prefix; the longest match wins.
+//!
+//! Events are printed as text by default. With `LOG_FORMAT=json` each event is
+//! one JSON object per line instead, with `timestamp`, `level`, `target`,
+//! `message` and the event's own fields as keys, for log aggregators.
+
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+/// Level used when `RUST_LOG` is unset or has no matching directive
+const DEFAULT_LEVEL: Level = Level::INFO;
+
+/// Environment variable selecting the output format
+const LOG_FORMAT_ENV: &str = "LOG_FORMAT";
+
+/// How events are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// "LEVEL target: message key=value ...", for reading in a terminal
+    #[default]
+    Pretty,
+    /// One JSON object per line
+    Json,
+}
+
+impl LogFormat {
+    /// Format named by a `LOG_FORMAT` value; anything but "json" is pretty
+    pub fn parse(value: &str) -> Self {
+        if value.trim().eq_ignore_ascii_case("json") {
+            Self::Json
+        } else {
+            Self::Pretty
+        }
+    }
+}
+
+/// Parsed `RUST_LOG` value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFilter {
+    default: Option<Level>,
+    /// (target prefix, max level), `None` turns the target off
+    targets: Vec<(String, Option<Level>)>,
+}
+
+impl LogFilter {
+    /// Parse a `RUST_LOG` style filter; unrecognized directives are ignored
+    pub fn parse(spec: &str) -> Self {
+        let mut filter = Self {
+            default: Some(DEFAULT_LEVEL),
+            targets: Vec::new(),
+        };
+
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((target, level)) => {
+                    if let Some(level) = Self::parse_level(level) {
+                        filter.targets.push((target.to_string(), level));
+                    }
+                }
+                None => match Self::parse_level(directive) {
+                    Some(level) => filter.default = level,
+                    // A bare target enables everything for it
+                    None => filter.targets.push((directive.to_string(), Some(Level::TRACE))),
+                },
+            }
+        }
+
+        filter
+    }
+
+    /// `Some(None)` for "off", `None` for anything that isn't a level
+    fn parse_level(s: &str) -> Option<Option<Level>> {
+        match s.trim().to_lowercase().as_str() {
+            "off" => Some(None),
+            "error" => Some(Some(Level::ERROR)),
+            "warn" => Some(Some(Level::WARN)),
+            "info" => Some(Some(Level::INFO)),
+            "debug" => Some(Some(Level::DEBUG)),
+            "trace" => Some(Some(Level::TRACE)),
+            _ => None,
+        }
+    }
+
+    /// Whether an event at `level` from `target` should be printed
+    pub fn enabled(&self, target: &str, level: &Level) -> bool {
+        let max_level = self
+            .targets
+            .iter()
+            .filter(|(prefix, _)| target.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default);
+
+        // More verbose levels compare greater
+        max_level.is_some_and(|max_level| *level <= max_level)
+    }
+}
+
+/// Writes one formatted log line
+type LineWriter = Box<dyn Fn(&str) + Send + Sync>;
+
+/// Prints events to stderr in the chosen `LogFormat`
+pub struct StderrSubscriber {
+    filter: LogFilter,
+    format: LogFormat,
+    write_line: LineWriter,
+    next_span_id: AtomicU64,
+}
+
+impl StderrSubscriber {
+    pub fn new(filter: LogFilter, format: LogFormat) -> Self {
+        Self::with_writer(filter, format, Box::new(|line| eprintln!("{}", line)))
+    }
+
+    /// Send lines to `write_line` instead of stderr
+    fn with_writer(filter: LogFilter, format: LogFormat, write_line: LineWriter) -> Self {
+        Self {
+            filter,
+            format,
+            write_line,
+            next_span_id: AtomicU64::new(1),
+        }
+    }
+
+    fn pretty_line(event: &Event<'_>) -> String {
+        let mut line = format!("{} {}:", event.metadata().level(), event.metadata().target());
+        event.record(&mut LineVisitor(&mut line));
+        line
+    }
+
+    fn json_line(event: &Event<'_>) -> String {
+        let mut object = serde_json::Map::new();
+        object.insert("timestamp".to_string(), chrono::Utc::now().to_rfc3339().into());
+        object.insert("level".to_string(), event.metadata().level().as_str().into());
+        object.insert("target".to_string(), event.metadata().target().into());
+        event.record(&mut JsonVisitor(&mut object));
+        serde_json::Value::Object(object).to_string()
+    }
+}
+
+impl Subscriber for StderrSubscriber {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.filter.enabled(metadata.target(), metadata.level())
+    }
+
+    fn new_span(&self, _span: &Attributes<'_>) -> Id {
+        Id::from_u64(self.next_span_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let line = match self.format {
+            LogFormat::Pretty => Self::pretty_line(event),
+            LogFormat::Json => Self::json_line(event),
+        };
+        (self.write_line)(&line);
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+/// Appends event fields to a log line; the message goes first, unquoted
+struct LineVisitor<'a>(&'a mut String);
+
+impl Visit for LineVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, " {:?}", value);
+        } else {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            let _ = write!(self.0, " {}", value);
+        } else {
+            let _ = write!(self.0, " {}={}", field.name(), value);
+        }
+    }
+}
+
+/// Collects event fields into a JSON object, keeping numbers and booleans typed
+struct JsonVisitor<'a>(&'a mut serde_json::Map<String, serde_json::Value>);
+
+impl JsonVisitor<'_> {
+    fn insert(&mut self, field: &Field, value: serde_json::Value) {
+        self.0.insert(field.name().to_string(), value);
+    }
+}
+
+impl Visit for JsonVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.insert(field, format!("{:?}", value).into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value.into());
+    }
+}
+
+/// Install the stderr subscriber globally, configured from `RUST_LOG` and `LOG_FORMAT`
+/// Does nothing if a subscriber is already installed
+pub fn init() {
+    let filter = LogFilter::parse(&std::env::var("RUST_LOG").unwrap_or_default());
+    let format = LogFormat::parse(&std::env::var(LOG_FORMAT_ENV).unwrap_or_default());
+    let _ = tracing::subscriber::set_global_default(StderrSubscriber::new(filter, format));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{fetcher_builder, MockResponse, MockServer};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_default_filter_is_info() {
+        let filter = LogFilter::parse("");
+        assert!(filter.enabled("talent_heron_lib::fetcher", &Level::INFO));
+        assert!(filter.enabled("talent_heron_lib::fetcher", &Level::WARN));
+        assert!(!filter.enabled("talent_heron_lib::fetcher", &Level::DEBUG));
+    }
+
+    #[test]
+    fn test_target_directives_override_the_default() {
+        let filter = LogFilter::parse("warn,talent_heron_lib::fetcher=debug,hyper=off");
+        assert!(filter.enabled("talent_heron_lib::fetcher", &Level::DEBUG));
+        assert!(!filter.enabled("talent_heron_lib::cache", &Level::INFO));
+        assert!(filter.enabled("talent_heron_lib::cache", &Level::WARN));
+        assert!(!filter.enabled("hyper::client", &Level::ERROR));
+    }
+
+    #[test]
+    fn test_log_format_defaults_to_pretty() {
+        assert_eq!(LogFormat::parse(""), LogFormat::Pretty);
+        assert_eq!(LogFormat::parse("text"), LogFormat::Pretty);
+        assert_eq!(LogFormat::parse(" JSON "), LogFormat::Json);
+    }
+
+    #[tokio::test]
+    async fn test_json_fetch_event_has_structured_fields() {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let captured = lines.clone();
+        let subscriber = StderrSubscriber::with_writer(
+            LogFilter::parse("info"),
+            LogFormat::Json,
+            Box::new(move |line| captured.lock().unwrap().push(line.to_string())),
+        );
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let server = MockServer::start().await;
+        server.respond("/missing", MockResponse::status(404));
+        let url = server.url("/missing");
+        fetcher_builder().max_retries(0).build().unwrap().fetch_talent_build(&url).await.unwrap();
+
+        let events: Vec<serde_json::Value> = lines
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|line| serde_json::from_str(line).expect("every line is JSON"))
+            .collect();
+        let fetched = events
+            .iter()
+            .find(|event| event["message"] == "Fetched talent build")
+            .expect("fetch event was logged");
+
+        assert_eq!(fetched["level"], "INFO");
+        assert_eq!(fetched["target"], "talent_heron_lib::fetcher");
+        assert_eq!(fetched["url"], url.as_str());
+        assert_eq!(fetched["status"], 404);
+        assert!(fetched["duration_ms"].is_u64());
+        assert_eq!(fetched["outcome"], "http-error");
+        assert!(fetched["timestamp"].is_string());
+    }
+}
@@ -0,0 +1,115 @@
+//! Fixed-capacity map that evicts the least recently used entry on overflow
+
+use std::borrow::Borrow;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+/// Least-recently-used cache; a capacity of 0 stores nothing
+#[derive(Debug)]
+pub struct LruCache<K, V> {
+    capacity: usize,
+    entries: HashMap<K, V>,
+    /// Keys from least to most recently used
+    order: VecDeque<K>,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> LruCache<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Value for `key`, marking it as the most recently used
+    pub fn get<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let value = self.entries.get(key)?.clone();
+        self.touch(key);
+        Some(value)
+    }
+
+    /// Store `value`, evicting the least recently used entry if the cache is full
+    pub fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.entries.insert(key.clone(), value).is_some() {
+            self.touch(&key);
+            return;
+        }
+
+        self.order.push_back(key);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    /// Forget every entry, returning their keys
+    pub fn clear(&mut self) -> Vec<K> {
+        self.entries.clear();
+        self.order.drain(..).collect()
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn touch<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        if let Some(position) = self.order.iter().position(|k| k.borrow() == key) {
+            if let Some(k) = self.order.remove(position) {
+                self.order.push_back(k);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_least_recently_used_is_evicted() {
+        let mut cache = LruCache::new(2);
+        cache.insert("a".to_string(), 1);
+        cache.insert("b".to_string(), 2);
+
+        // Reading "a" makes "b" the oldest
+        assert_eq!(cache.get("a"), Some(1));
+        cache.insert("c".to_string(), 3);
+
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("a"), Some(1));
+        assert_eq!(cache.get("c"), Some(3));
+        assert_eq!(cache.len(), 2);
+
+        // Overwriting refreshes without growing
+        cache.insert("a".to_string(), 10);
+        cache.insert("d".to_string(), 4);
+        assert_eq!(cache.get("a"), Some(10));
+        assert_eq!(cache.get("c"), None);
+
+        assert_eq!(cache.clear().len(), 2);
+        assert_eq!(cache.get("a"), None);
+    }
+
+    #[test]
+    fn test_zero_capacity_stores_nothing() {
+        let mut cache = LruCache::new(0);
+        cache.insert("a", 1);
+        assert_eq!(cache.get("a"), None);
+        assert_eq!(cache.len(), 0);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::atomic_file::write_atomically;
 use anyhow::{Context, Result};
 use full_moon::ast::{Expression, Field, Stmt, TableConstructor, Var};
 use std::collections::HashMap;
@@ -31,6 +32,7 @@ impl TalentLoadout {
 pub type ClassTalents = HashMap<u8, Vec<TalentLoadout>>;
 
 /// Manager for reading and writing TalentLoadoutsEx.lua files
+#[derive(Clone)]
 pub struct LuaTalentManager {
     /// All talents organized by class name (e.g., "WARRIOR", "MAGE")
     talents: HashMap<String, ClassTalents>,
@@ -220,7 +222,28 @@ impl LuaTalentManager {
             .insert(spec_index, talents);
     }
 
+    /// All talents as (class name, spec index, talent), in no particular class or spec order
+    pub fn all_talents(&self) -> Vec<(&str, u8, &TalentLoadout)> {
+        self.talents
+            .iter()
+            .flat_map(|(class_name, class_talents)| {
+                class_talents.iter().flat_map(move |(spec_index, talents)| {
+                    talents.iter().map(move |t| (class_name.as_str(), *spec_index, t))
+                })
+            })
+            .collect()
+    }
+
+    /// All auto-generated talents as (class name, spec index, talent)
+    pub fn auto_generated_talents(&self) -> Vec<(&str, u8, &TalentLoadout)> {
+        self.all_talents()
+            .into_iter()
+            .filter(|(_, _, t)| t.is_auto_generated())
+            .collect()
+    }
+
     /// Remove all auto-generated talents (with _ARCT suffix) for a specific class/spec
+    #[allow(dead_code)]
     pub fn remove_auto_generated(&mut self, class_name: &str, spec_index: u8) {
         if let Some(class_talents) = self.talents.get_mut(class_name) {
             if let Some(spec_talents) = class_talents.get_mut(&spec_index) {
@@ -229,6 +252,15 @@ impl LuaTalentManager {
         }
     }
 
+    /// Remove the auto-generated talents of a class/spec whose name starts with `prefix`
+    pub fn remove_auto_generated_with_prefix(&mut self, class_name: &str, spec_index: u8, prefix: &str) {
+        if let Some(class_talents) = self.talents.get_mut(class_name) {
+            if let Some(spec_talents) = class_talents.get_mut(&spec_index) {
+                spec_talents.retain(|t| !(t.is_auto_generated() && t.name.starts_with(prefix)));
+            }
+        }
+    }
+
     /// Remove all auto-generated talents across all classes and specs
     pub fn remove_all_auto_generated(&mut self) {
         for class_talents in self.talents.values_mut() {
@@ -249,9 +281,10 @@ impl LuaTalentManager {
     }
 
     /// Write talents to a Lua file
+    /// The file is replaced atomically, so an interrupted write keeps the old talents
     pub fn write_to_file(&self, path: impl AsRef<Path>) -> Result<()> {
         let lua_content = self.to_lua_string();
-        std::fs::write(path.as_ref(), lua_content)
+        write_atomically(path.as_ref(), lua_content)
             .context("Failed to write TalentLoadoutsEx.lua file")?;
         Ok(())
     }
@@ -0,0 +1,116 @@
+use crate::fetcher::FetchOutcome;
+use serde::Serialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Counters and request latencies accumulated by an `ArchonFetcher`
+#[derive(Debug, Default)]
+pub struct FetchMetrics {
+    found: AtomicUsize,
+    no_data: AtomicUsize,
+    no_talent_link: AtomicUsize,
+    http_errors: AtomicUsize,
+    network_errors: AtomicUsize,
+    blocked: AtomicUsize,
+    unexpected_content_types: AtomicUsize,
+    spec_mismatches: AtomicUsize,
+    unreadable_bodies: AtomicUsize,
+    cache_hits: AtomicUsize,
+    /// Time from sending the first attempt to parsing the page, per network fetch
+    latencies: Mutex<Vec<Duration>>,
+}
+
+/// Point-in-time copy of `FetchMetrics`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchMetricsSnapshot {
+    pub found: usize,
+    pub no_data: usize,
+    pub no_talent_link: usize,
+    pub http_errors: usize,
+    pub network_errors: usize,
+    pub blocked: usize,
+    /// Responses that weren't HTML
+    pub unexpected_content_types: usize,
+    /// Pages whose build was for a different spec than requested
+    pub spec_mismatches: usize,
+    /// Bodies that were too large or couldn't be decoded
+    pub unreadable_bodies: usize,
+    /// Builds served from the cache without a request (not counted as found)
+    pub cache_hits: usize,
+    pub requests: usize,
+    pub p50_latency_ms: Option<u64>,
+    pub p95_latency_ms: Option<u64>,
+}
+
+impl FetchMetrics {
+    /// Count the outcome of a network fetch and how long it took
+    pub fn record(&self, outcome: &FetchOutcome, elapsed: Duration) {
+        let counter = match outcome {
+            FetchOutcome::Found(_) => &self.found,
+            FetchOutcome::NoData => &self.no_data,
+            FetchOutcome::NoTalentLink => &self.no_talent_link,
+            FetchOutcome::HttpError(_) => &self.http_errors,
+            FetchOutcome::Network(_) => &self.network_errors,
+            FetchOutcome::Blocked => &self.blocked,
+            FetchOutcome::UnexpectedContentType(_) => &self.unexpected_content_types,
+            FetchOutcome::SpecMismatch { .. } => &self.spec_mismatches,
+            FetchOutcome::UnreadableBody(_) => &self.unreadable_bodies,
+            // Never sent, so there is nothing to count
+            FetchOutcome::OfflineMiss | FetchOutcome::BudgetExceeded => return,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        self.latencies.lock().unwrap().push(elapsed);
+    }
+
+    /// Count a build served from the cache
+    pub fn record_cache_hit(&self) {
+        self.cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> FetchMetricsSnapshot {
+        let mut latencies = self.latencies.lock().unwrap().clone();
+        latencies.sort();
+
+        FetchMetricsSnapshot {
+            found: self.found.load(Ordering::Relaxed),
+            no_data: self.no_data.load(Ordering::Relaxed),
+            no_talent_link: self.no_talent_link.load(Ordering::Relaxed),
+            http_errors: self.http_errors.load(Ordering::Relaxed),
+            network_errors: self.network_errors.load(Ordering::Relaxed),
+            blocked: self.blocked.load(Ordering::Relaxed),
+            unexpected_content_types: self.unexpected_content_types.load(Ordering::Relaxed),
+            spec_mismatches: self.spec_mismatches.load(Ordering::Relaxed),
+            unreadable_bodies: self.unreadable_bodies.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            requests: latencies.len(),
+            p50_latency_ms: percentile(&latencies, 50),
+            p95_latency_ms: percentile(&latencies, 95),
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted latencies, in milliseconds
+fn percentile(sorted: &[Duration], percent: usize) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+
+    let rank = (percent * sorted.len()).div_ceil(100).max(1);
+    Some(sorted[rank - 1].as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles_use_nearest_rank() {
+        let latencies: Vec<_> = (1..=20).map(|ms| Duration::from_millis(ms * 10)).collect();
+        assert_eq!(percentile(&latencies, 50), Some(100));
+        assert_eq!(percentile(&latencies, 95), Some(190));
+        assert_eq!(percentile(&latencies[..1], 95), Some(10));
+        assert_eq!(percentile(&[], 50), None);
+    }
+}
@@ -1,10 +1,18 @@
-use crate::archon::{ArchonUrlBuilder, MythicPlusTimespan, RaidDifficulty, TalentIdentifier};
-use crate::config::Config;
-use crate::fetcher::ArchonFetcher;
+use crate::archon::{ArchonUrlBuilder, ContentType, MythicPlusTimespan, RaidDifficulty, TalentIdentifier};
+use crate::atomic_file;
+use crate::backup;
+use crate::config::{Character, Config};
+use crate::fetcher::{ArchonFetcher, FetchOutcome};
 use crate::lua_talent::{LuaTalentManager, TalentLoadout};
-use crate::wow::WowClass;
+use crate::talent_validation::{self, SpecKey};
+use crate::wow::{Spec, WowClass};
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tokio_util::sync::CancellationToken;
 
 /// Summary of the talent update operation
 #[derive(Debug, Serialize)]
@@ -13,6 +21,154 @@ pub struct UpdateSummary {
     pub raid_talents: usize,
     pub mythic_plus_talents: usize,
     pub characters_processed: usize,
+    /// The run was cancelled; builds fetched before that are still written
+    pub cancelled: bool,
+    /// Builds whose fetched talent string is new or differs from the file
+    pub builds_changed: usize,
+    /// Whether the output file was rewritten (it is left alone when nothing changed)
+    pub file_written: bool,
+    /// Builds below `min_popularity`, left as they were in the file
+    pub builds_skipped: usize,
+    /// Specs whose raid or Mythic+ builds were fetched within `refresh_interval_hours`,
+    /// counted once per content type and left as they were in the file
+    pub specs_up_to_date: usize,
+    /// Response body bytes downloaded during the run
+    pub bytes_downloaded: usize,
+    /// Builds filled in from another content type under `content_preference`
+    pub content_fallbacks: Vec<ContentFallback>,
+    /// Build URLs that redirected elsewhere, so stored links can be updated
+    pub redirects: Vec<Redirect>,
+}
+
+/// A build URL that Archon answered from another URL
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Redirect {
+    /// URL that was requested
+    pub requested: String,
+    /// URL after following redirects
+    pub final_url: String,
+}
+
+/// A missing build filled in with the overview build of another content type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentFallback {
+    /// Class in Lua format (e.g. "MAGE")
+    pub class: String,
+    /// Spec name (e.g. "frost")
+    pub spec: String,
+    /// Content type whose build was missing
+    pub requested: ContentType,
+    /// Content type whose build was used instead
+    pub used: ContentType,
+}
+
+/// How one auto-generated talent would change in a dry run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildDiff {
+    /// Class in Lua format (e.g. "MAGE")
+    pub class: String,
+    /// Spec name (e.g. "frost"), or the spec index if the class is unknown
+    pub spec: String,
+    /// Talent name (e.g. "R-heroic-sikran_ARCT")
+    pub build: String,
+    /// Talent string currently in the file
+    pub old: Option<String>,
+    /// Talent string the update would write
+    pub new: Option<String>,
+    pub changed: bool,
+}
+
+/// Class, spec index and talent name of an auto-generated talent
+type BuildKey = (String, u8, String);
+/// Talent strings before and after the update
+type BuildTexts = (Option<String>, Option<String>);
+
+/// Compare the auto-generated talents of two talent sets, sorted by class, spec and name
+pub fn diff_builds(existing: &LuaTalentManager, updated: &LuaTalentManager) -> Vec<BuildDiff> {
+    let mut builds: BTreeMap<BuildKey, BuildTexts> = BTreeMap::new();
+
+    for (class, spec_index, talent) in existing.auto_generated_talents() {
+        builds
+            .entry((class.to_string(), spec_index, talent.name.clone()))
+            .or_default()
+            .0 = Some(talent.text.clone());
+    }
+    for (class, spec_index, talent) in updated.auto_generated_talents() {
+        builds
+            .entry((class.to_string(), spec_index, talent.name.clone()))
+            .or_default()
+            .1 = Some(talent.text.clone());
+    }
+
+    builds
+        .into_iter()
+        .map(|((class, spec_index, build), (old, new))| {
+            let spec = WowClass::from_lua_format(&class)
+                .and_then(|wow_class| wow_class.specs().into_iter().find(|spec| spec.index() == spec_index))
+                .map(|spec| spec.name().to_string())
+                .unwrap_or_else(|| spec_index.to_string());
+
+            BuildDiff {
+                changed: old != new,
+                class,
+                spec,
+                build,
+                old,
+                new,
+            }
+        })
+        .collect()
+}
+
+/// Progress report sent after each build is fetched (found or not)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildProgress {
+    /// Class in Lua format (e.g. "MAGE")
+    pub class: String,
+    pub spec: String,
+    /// Build identifier (e.g. "R-heroic-sikran" or "M+-ara-kara")
+    pub build: String,
+    pub completed: usize,
+    pub total: usize,
+}
+
+/// Receives a `BuildProgress` after each build
+pub type ProgressCallback = Box<dyn Fn(BuildProgress) + Send + Sync>;
+
+/// Talents added and builds skipped by `update_talents`
+#[derive(Debug, Default)]
+struct TalentCounts {
+    raid_talents: usize,
+    mythic_plus_talents: usize,
+    builds_skipped: usize,
+    specs_up_to_date: usize,
+    /// Character index, spec name and content type of every spec whose builds
+    /// of that content type were all fetched
+    fetched_specs: Vec<(usize, String, ContentType)>,
+}
+
+/// Content types `update_talents` fetches builds for, in order
+pub const FETCHED_CONTENT: [ContentType; 2] = [ContentType::Raid, ContentType::MythicPlus];
+
+/// Talent name for an overview build, after the content type prefix (e.g. "R-overview_ARCT")
+const OVERVIEW_BUILD_NAME: &str = "overview";
+
+/// Result of fetching one build page
+enum FetchedBuild {
+    Found(String),
+    /// Found, but used by too few players to replace the current build
+    Unpopular(f32),
+    Missing,
+}
+
+/// Running count of builds fetched during `run`
+struct ProgressCounter {
+    completed: usize,
+    total: usize,
 }
 
 /// Orchestrates the entire talent fetch and update process
@@ -20,107 +176,508 @@ pub struct TalentOrchestrator {
     config: Config,
     fetcher: ArchonFetcher,
     url_builder: ArchonUrlBuilder,
+    progress: Option<ProgressCallback>,
+    cancel: CancellationToken,
+    config_path: Option<PathBuf>,
+    /// Redirects seen by `fetch_build` during the current run
+    redirects: Mutex<Vec<Redirect>>,
 }
 
 impl TalentOrchestrator {
     /// Create a new orchestrator with the given configuration
+    #[allow(dead_code)]
     pub fn new(config: Config) -> Self {
+        Self::with_fetcher(config, ArchonFetcher::new())
+    }
+
+    /// Create a new orchestrator that fetches builds through `fetcher`
+    pub fn with_fetcher(config: Config, fetcher: ArchonFetcher) -> Self {
         Self {
             config,
-            fetcher: ArchonFetcher::new(),
+            fetcher,
             url_builder: ArchonUrlBuilder::new(),
+            progress: None,
+            cancel: CancellationToken::new(),
+            config_path: None,
+            redirects: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Save the config back to `path` after a run, with each fetched spec's `last_fetched` updated
+    pub fn with_config_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_path = Some(path.into());
+        self
+    }
+
+    /// Write talents to `path` instead of the config's `output_path`
+    pub fn with_output_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.output_path = path.into();
+        self
+    }
+
+    /// Whether the `content` builds of `spec` were fetched recently enough to skip
+    /// under `refresh_interval_hours`
+    fn is_up_to_date(&self, character: &Character, spec: &str, content: ContentType, now: DateTime<Utc>) -> bool {
+        self.config
+            .refresh_interval()
+            .is_some_and(|interval| character.is_fetched_within(spec, content, interval, now))
+    }
+
+    /// Number of `content` builds configured for each spec
+    fn builds_per_spec(&self, content: ContentType) -> usize {
+        match content {
+            ContentType::Raid => self.config.raid_bosses.len() * self.config.raid_difficulties.len(),
+            ContentType::MythicPlus => self.config.dungeons.len(),
+            ContentType::Pvp => 0,
+        }
+    }
+
+    /// Build URLs with `url_builder` instead of the default Archon.gg one
+    #[allow(dead_code)]
+    pub fn with_url_builder(mut self, url_builder: ArchonUrlBuilder) -> Self {
+        self.url_builder = url_builder;
+        self
+    }
+
+    /// Stop fetching once `cancel` is triggered
+    /// Builds fetched so far are still written to the output file
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Fetch a build page, giving up on the request if the run is cancelled
+    /// Builds without a known popularity are never treated as unpopular
+    /// Redirects are recorded for the run's summary
+    async fn fetch_build(&self, url: &str) -> Result<FetchedBuild> {
+        let page = tokio::select! {
+            biased;
+            _ = self.cancel.cancelled() => return Ok(FetchedBuild::Missing),
+            result = self.fetcher.fetch_page(url) => result?,
+        };
+
+        if page.final_url != url {
+            tracing::info!(url, final_url = %page.final_url, "Build URL redirected");
+            self.redirects.lock().unwrap().push(Redirect {
+                requested: url.to_string(),
+                final_url: page.final_url.clone(),
+            });
+        }
+
+        let Some(talent_string) = page.outcome.into_talent_string() else {
+            return Ok(FetchedBuild::Missing);
+        };
+        match (page.popularity, self.config.min_popularity) {
+            (Some(popularity), Some(min_popularity)) if popularity < min_popularity => {
+                Ok(FetchedBuild::Unpopular(popularity))
+            }
+            _ => Ok(FetchedBuild::Found(talent_string)),
+        }
+    }
+
+    /// Put the talent called `name` from `previous` back into `talent_manager`
+    fn keep_previous_build(
+        talent_manager: &mut LuaTalentManager,
+        previous: &LuaTalentManager,
+        wow_class: WowClass,
+        spec_index: u8,
+        name: &str,
+    ) {
+        let class_name = wow_class.to_lua_format();
+        let talent = previous
+            .get_spec_talents(class_name, spec_index)
+            .and_then(|talents| talents.iter().find(|talent| talent.name == name));
+
+        if let Some(talent) = talent {
+            talent_manager.add_talent(class_name.to_string(), spec_index, talent.clone());
+        }
+    }
+
+    /// Put the auto-generated talents of a spec whose name starts with `prefix`
+    /// from `previous` back into `talent_manager`
+    fn keep_previous_content(
+        talent_manager: &mut LuaTalentManager,
+        previous: &LuaTalentManager,
+        wow_class: WowClass,
+        spec_index: u8,
+        prefix: &str,
+    ) {
+        let class_name = wow_class.to_lua_format();
+        let talents = previous.get_spec_talents(class_name, spec_index).into_iter().flatten();
+
+        for talent in talents.filter(|talent| talent.is_auto_generated() && talent.name.starts_with(prefix)) {
+            talent_manager.add_talent(class_name.to_string(), spec_index, talent.clone());
+        }
+    }
+
+    /// Call `callback` with a `BuildProgress` after each build is fetched
+    pub fn with_progress(mut self, callback: impl Fn(BuildProgress) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    /// Number of builds `run` will fetch, counting a Mythic+ fallback as part of its dungeon
+    fn total_builds(&self) -> usize {
+        let now = Utc::now();
+
+        self.config
+            .characters
+            .iter()
+            .flat_map(|character| character.specializations.iter().map(move |spec| (character, spec)))
+            .flat_map(|(character, spec)| FETCHED_CONTENT.map(|content| (character, spec, content)))
+            .filter(|(character, spec, content)| !self.is_up_to_date(character, spec, *content, now))
+            .map(|(_, _, content)| self.builds_per_spec(content))
+            .sum()
+    }
+
+    fn report_progress(
+        &self,
+        counter: &mut ProgressCounter,
+        wow_class: WowClass,
+        spec: &str,
+        identifier: &TalentIdentifier,
+    ) {
+        counter.completed += 1;
+
+        if let Some(callback) = &self.progress {
+            callback(BuildProgress {
+                class: wow_class.to_lua_format().to_string(),
+                spec: spec.to_string(),
+                build: identifier.as_identifier(),
+                completed: counter.completed,
+                total: counter.total,
+            });
         }
     }
 
     /// Run the full talent update process
     pub async fn run(&self) -> Result<UpdateSummary> {
-        println!("Starting talent fetch from Archon.gg...");
+        tracing::info!("Starting talent fetch from Archon.gg");
+        let bytes_before = self.fetcher.bytes_downloaded();
 
-        let mut raid_talents = 0;
-        let mut mythic_plus_talents = 0;
+        // Fail before fetching anything rather than after
+        atomic_file::ensure_writable(&self.config.output_path)
+            .with_context(|| format!("Cannot write talents to {:?}", self.config.output_path))?;
 
-        // Load existing talents
-        let mut talent_manager = if self.config.output_path.exists() {
-            println!("Loading existing talents from {:?}", self.config.output_path);
+        let existing = self.load_existing_talents()?;
+        let mut talent_manager = existing.clone();
+        let counts = self.update_talents(&mut talent_manager).await?;
+
+        // Builds that weren't found keep nothing to compare against, so only
+        // fetched builds that differ from the file count as a change
+        let builds_changed = diff_builds(&existing, &talent_manager)
+            .iter()
+            .filter(|diff| diff.changed && diff.new.is_some())
+            .count();
+        let file_written = builds_changed > 0;
+
+        if file_written {
+            self.write_talents(&talent_manager)?;
+        } else {
+            tracing::info!(path = ?self.config.output_path, "No builds changed, leaving the talent file untouched");
+        }
+
+        if let Some(config_path) = &self.config_path {
+            self.record_fetched_specs(config_path, &counts.fetched_specs)?;
+        }
+
+        let summary = UpdateSummary {
+            total_talents_updated: counts.raid_talents + counts.mythic_plus_talents,
+            raid_talents: counts.raid_talents,
+            mythic_plus_talents: counts.mythic_plus_talents,
+            characters_processed: self.config.characters.len(),
+            cancelled: self.cancel.is_cancelled(),
+            builds_changed,
+            file_written,
+            builds_skipped: counts.builds_skipped,
+            specs_up_to_date: counts.specs_up_to_date,
+            bytes_downloaded: self.fetcher.bytes_downloaded() - bytes_before,
+            content_fallbacks: Vec::new(),
+            redirects: std::mem::take(&mut *self.redirects.lock().unwrap()),
+        };
+
+        tracing::info!(
+            total = summary.total_talents_updated,
+            raid = summary.raid_talents,
+            mythic_plus = summary.mythic_plus_talents,
+            "Talent fetch complete"
+        );
+
+        Ok(summary)
+    }
+
+    /// Fetch the overview build of every spec the output file has no Raid or
+    /// Mythic+ build for yet, leaving every talent already in it untouched
+    /// The config's characters, bosses and dungeons are not consulted
+    pub async fn fill_missing(&self) -> Result<UpdateSummary> {
+        atomic_file::ensure_writable(&self.config.output_path)
+            .with_context(|| format!("Cannot write talents to {:?}", self.config.output_path))?;
+
+        let mut talent_manager = self.load_existing_talents()?;
+        let missing: BTreeSet<SpecKey> = talent_validation::missing_specs(&talent_manager, &FETCHED_CONTENT)
+            .into_iter()
+            .collect();
+
+        let mut targets = Vec::new();
+        for content in FETCHED_CONTENT {
+            for (spec, url) in self.url_builder.all_overview_urls(content) {
+                let key = SpecKey {
+                    class_name: spec.class().to_lua_format().to_string(),
+                    spec_index: spec.index(),
+                    name: content.as_path().to_string(),
+                };
+                if missing.contains(&key) {
+                    let url = self.config.url_override(spec, content).map_or(url, str::to_string);
+                    targets.push((spec, key, content, url));
+                }
+            }
+        }
+        tracing::info!(builds = targets.len(), "Fetching missing builds from Archon.gg");
+
+        let urls: Vec<String> = targets.iter().map(|(_, _, _, url)| url.clone()).collect();
+        let bytes_before = self.fetcher.bytes_downloaded();
+        let results = self.fetcher.fetch_many(&urls).await;
+
+        let (mut raid_talents, mut mythic_plus_talents) = (0, 0);
+        let mut content_fallbacks = Vec::new();
+        for ((spec, key, content, _), (_, result)) in targets.into_iter().zip(results) {
+            let mut outcome = result.ok();
+            if let (Some(FetchOutcome::NoData), Some(fallbacks)) = (&outcome, self.fallback_content(content)) {
+                match self.fetcher.fetch_preferred(spec, fallbacks).await {
+                    Ok(preferred) => {
+                        if matches!(preferred.outcome, FetchOutcome::Found(_)) {
+                            content_fallbacks.push(ContentFallback {
+                                class: key.class_name.clone(),
+                                spec: spec.name().to_string(),
+                                requested: content,
+                                used: preferred.content,
+                            });
+                        }
+                        outcome = Some(preferred.outcome);
+                    }
+                    Err(e) => tracing::warn!(spec = spec.name(), "Failed to fetch fallback build: {:#}", e),
+                }
+            }
+
+            let (Some(talent_string), Some(prefix)) = (
+                outcome.and_then(FetchOutcome::into_talent_string),
+                content.talent_name_prefix(),
+            ) else {
+                continue;
+            };
+
+            let name = format!("{}{}_ARCT", prefix, OVERVIEW_BUILD_NAME);
+            talent_manager.add_talent(key.class_name, key.spec_index, TalentLoadout::new(name, talent_string));
+            match content {
+                ContentType::Raid => raid_talents += 1,
+                ContentType::MythicPlus => mythic_plus_talents += 1,
+                ContentType::Pvp => {}
+            }
+        }
+
+        let bytes_downloaded = self.fetcher.bytes_downloaded() - bytes_before;
+
+        let builds_changed = raid_talents + mythic_plus_talents;
+        let file_written = builds_changed > 0;
+        if file_written {
+            self.write_talents(&talent_manager)?;
+        }
+
+        Ok(UpdateSummary {
+            total_talents_updated: builds_changed,
+            raid_talents,
+            mythic_plus_talents,
+            characters_processed: 0,
+            cancelled: false,
+            builds_changed,
+            file_written,
+            builds_skipped: 0,
+            specs_up_to_date: 0,
+            bytes_downloaded,
+            content_fallbacks,
+            redirects: Vec::new(),
+        })
+    }
+
+    /// Content types to try after `content` has no data, in order of preference
+    fn fallback_content(&self, content: ContentType) -> Option<&[ContentType]> {
+        let preference = &self.config.content_preference;
+        let position = preference.iter().position(|preferred| *preferred == content)?;
+        Some(&preference[position + 1..]).filter(|fallbacks| !fallbacks.is_empty())
+    }
+
+    /// Back up the output file, then replace it with `talent_manager`'s talents
+    fn write_talents(&self, talent_manager: &LuaTalentManager) -> Result<()> {
+        // Keep a copy of the current file in case the update wipes builds
+        if let Some(backup_path) = backup::create_backup(&self.config.output_path, self.config.backup_count)
+            .context("Failed to back up existing talents")?
+        {
+            tracing::info!(backup = ?backup_path, "Backed up existing talents");
+        }
+
+        // Write updated talents back to file
+        tracing::info!(path = ?self.config.output_path, "Writing talents");
+        talent_manager
+            .write_to_file(&self.config.output_path)
+            .context("Failed to write talents to file")
+    }
+
+    /// Save the config to `path` with `fetched_specs` marked as fetched now
+    fn record_fetched_specs(&self, path: &std::path::Path, fetched_specs: &[(usize, String, ContentType)]) -> Result<()> {
+        if fetched_specs.is_empty() {
+            return Ok(());
+        }
+
+        let now = Utc::now();
+        let mut config = self.config.clone();
+        for (character_index, spec, content) in fetched_specs {
+            config.characters[*character_index].mark_fetched(spec, *content, now);
+        }
+
+        config.save_to_file(path).context("Failed to save fetch times to config")
+    }
+
+    /// Override URL the config pins for `spec`'s `content` builds, if any
+    fn url_override(&self, wow_class: WowClass, spec: &str, content: ContentType) -> Option<String> {
+        let spec = Spec::from_class_and_name(wow_class, spec)?;
+        self.config.url_override(spec, content).map(str::to_string)
+    }
+
+    /// Existing talents from the output file, or an empty set if it doesn't exist yet
+    fn load_existing_talents(&self) -> Result<LuaTalentManager> {
+        if self.config.output_path.exists() {
+            tracing::info!(path = ?self.config.output_path, "Loading existing talents");
             LuaTalentManager::load_from_file(&self.config.output_path)
-                .context("Failed to load existing talents")?
+                .context("Failed to load existing talents")
         } else {
-            println!("No existing talent file found, creating new one");
-            LuaTalentManager::new()
+            tracing::info!(path = ?self.config.output_path, "No existing talent file found, creating a new one");
+            Ok(LuaTalentManager::new())
+        }
+    }
+
+    /// Fetch the configured builds into `talent_manager`, replacing its auto-generated ones
+    /// Unpopular builds keep the talent `talent_manager` had before
+    async fn update_talents(&self, talent_manager: &mut LuaTalentManager) -> Result<TalentCounts> {
+        let previous = talent_manager.clone();
+        let mut counts = TalentCounts::default();
+        let mut progress = ProgressCounter {
+            completed: 0,
+            total: self.total_builds(),
         };
+        let now = Utc::now();
 
         // Clear previous auto-generated builds if requested
         if self.config.clear_previous_builds {
-            println!("Clearing all previous auto-generated builds");
+            tracing::info!("Clearing all previous auto-generated builds");
             talent_manager.remove_all_auto_generated();
         }
 
         // Process each character
-        for character in &self.config.characters {
-            println!("\nProcessing character: {} ({})", character.name, character.class);
+        for (character_index, character) in self.config.characters.iter().enumerate() {
+            if self.cancel.is_cancelled() {
+                break;
+            }
+
+            tracing::info!(character = %character.name, class = %character.class, "Processing character");
 
             let wow_class = WowClass::from_str(&character.class)
                 .ok_or_else(|| anyhow::anyhow!("Invalid class: {}", character.class))?;
 
             for spec in &character.specializations {
-                println!("  Specialization: {}", spec);
+                if self.cancel.is_cancelled() {
+                    tracing::info!(character = %character.name, "Update cancelled");
+                    break;
+                }
+
+                tracing::info!(spec = %spec, "Processing specialization");
 
                 // Validate spec for this class
                 let spec_index = wow_class
                     .spec_index(spec)
                     .ok_or_else(|| anyhow::anyhow!("Invalid spec {} for class {}", spec, character.class))?;
 
-                // Clear auto-generated talents for this spec
-                if !self.config.clear_previous_builds {
-                    talent_manager.remove_auto_generated(wow_class.to_lua_format(), spec_index);
-                }
+                // Raid and Mythic+ builds of one spec are kept and refreshed separately
+                for content in FETCHED_CONTENT {
+                    let Some(prefix) = content.talent_name_prefix() else {
+                        continue;
+                    };
+                    if self.builds_per_spec(content) == 0 {
+                        continue;
+                    }
 
-                // Fetch raid builds
-                if !self.config.raid_bosses.is_empty() && !self.config.raid_difficulties.is_empty() {
-                    raid_talents += self.fetch_raid_builds(&mut talent_manager, wow_class, spec, spec_index)
-                        .await?;
-                }
+                    if self.is_up_to_date(character, spec, content, now) {
+                        tracing::info!(spec = %spec, content = content.as_path(), "Builds fetched recently, skipping");
+                        if self.config.clear_previous_builds {
+                            Self::keep_previous_content(talent_manager, &previous, wow_class, spec_index, prefix);
+                        }
+                        counts.specs_up_to_date += 1;
+                        continue;
+                    }
+
+                    // Clear auto-generated talents of this content type for this spec
+                    if !self.config.clear_previous_builds {
+                        talent_manager.remove_auto_generated_with_prefix(wow_class.to_lua_format(), spec_index, prefix);
+                    }
 
-                // Fetch Mythic+ builds
-                if !self.config.dungeons.is_empty() {
-                    mythic_plus_talents += self.fetch_mythic_plus_builds(&mut talent_manager, wow_class, spec, spec_index)
-                        .await?;
+                    let (added, skipped) = if content == ContentType::Raid {
+                        let (added, skipped) = self
+                            .fetch_raid_builds(talent_manager, &previous, &mut progress, wow_class, spec, spec_index)
+                            .await?;
+                        counts.raid_talents += added;
+                        (added, skipped)
+                    } else {
+                        let (added, skipped) = self
+                            .fetch_mythic_plus_builds(talent_manager, &previous, &mut progress, wow_class, spec, spec_index)
+                            .await?;
+                        counts.mythic_plus_talents += added;
+                        (added, skipped)
+                    };
+                    counts.builds_skipped += skipped;
+
+                    // A cancelled spec may be missing builds, and one whose fetches
+                    // all failed has nothing new, so both stay stale for a retry
+                    if !self.cancel.is_cancelled() && added + skipped > 0 {
+                        counts.fetched_specs.push((character_index, spec.clone(), content));
+                    }
                 }
             }
         }
 
-        // Write updated talents back to file
-        println!("\nWriting talents to {:?}", self.config.output_path);
-        talent_manager
-            .write_to_file(&self.config.output_path)
-            .context("Failed to write talents to file")?;
+        Ok(counts)
+    }
 
-        let summary = UpdateSummary {
-            total_talents_updated: raid_talents + mythic_plus_talents,
-            raid_talents,
-            mythic_plus_talents,
-            characters_processed: self.config.characters.len(),
-        };
+    /// Fetch builds like `run`, but only report how the auto-generated talents
+    /// would change instead of writing the output file
+    pub async fn dry_run(&self) -> Result<Vec<BuildDiff>> {
+        tracing::info!("Previewing talent update from Archon.gg");
 
-        println!("Talent fetch complete!");
-        println!("Summary: {} total talents updated ({} raid, {} M+)",
-            summary.total_talents_updated, summary.raid_talents, summary.mythic_plus_talents);
+        let existing = self.load_existing_talents()?;
+        let mut updated = existing.clone();
+        self.update_talents(&mut updated).await?;
 
-        Ok(summary)
+        Ok(diff_builds(&existing, &updated))
     }
 
     /// Fetch raid builds for a specific class/spec
+    /// Returns the number of talents added and of unpopular builds skipped
     async fn fetch_raid_builds(
         &self,
         talent_manager: &mut LuaTalentManager,
+        previous: &LuaTalentManager,
+        progress: &mut ProgressCounter,
         wow_class: WowClass,
         spec: &str,
         spec_index: u8,
-    ) -> Result<usize> {
+    ) -> Result<(usize, usize)> {
         let mut count = 0;
+        let mut skipped = 0;
 
         for boss in &self.config.raid_bosses {
             for difficulty_str in &self.config.raid_difficulties {
+                if self.cancel.is_cancelled() {
+                    return Ok((count, skipped));
+                }
+
                 let difficulty = RaidDifficulty::from_str(difficulty_str)
                     .ok_or_else(|| anyhow::anyhow!("Invalid difficulty: {}", difficulty_str))?;
 
@@ -129,58 +686,86 @@ impl TalentOrchestrator {
                     boss: boss.clone(),
                 };
 
-                let url = self.url_builder.build_raid_url(wow_class, spec, difficulty, boss);
+                let url = self
+                    .url_override(wow_class, spec, ContentType::Raid)
+                    .unwrap_or_else(|| self.url_builder.build_raid_url(wow_class, spec, difficulty, boss));
 
-                println!("    Fetching: {} from {}", identifier.as_identifier(), url);
+                tracing::debug!(build = %identifier.as_identifier(), url = %url, "Fetching build");
 
-                match self.fetcher.fetch_talent_build(&url).await? {
-                    Some(talent_string) => {
+                match self.fetch_build(&url).await? {
+                    FetchedBuild::Found(talent_string) => {
                         let talent = TalentLoadout::new(identifier.as_talent_name(), talent_string);
                         talent_manager.add_talent(
                             wow_class.to_lua_format().to_string(),
                             spec_index,
                             talent,
                         );
-                        println!("      Found talent build");
+                        tracing::debug!(build = %identifier.as_identifier(), "Found talent build");
                         count += 1;
                     }
-                    None => {
-                        println!("      No talent build available");
+                    FetchedBuild::Unpopular(popularity) => {
+                        tracing::info!(
+                            build = %identifier.as_identifier(),
+                            popularity,
+                            min_popularity = ?self.config.min_popularity,
+                            "Skipping unpopular talent build"
+                        );
+                        Self::keep_previous_build(
+                            talent_manager,
+                            previous,
+                            wow_class,
+                            spec_index,
+                            &identifier.as_talent_name(),
+                        );
+                        skipped += 1;
+                    }
+                    FetchedBuild::Missing => {
+                        tracing::info!(build = %identifier.as_identifier(), "No talent build available");
                     }
                 }
+
+                self.report_progress(progress, wow_class, spec, &identifier);
             }
         }
 
-        Ok(count)
+        Ok((count, skipped))
     }
 
     /// Fetch Mythic+ builds for a specific class/spec
+    /// Returns the number of talents added and of unpopular builds skipped
     async fn fetch_mythic_plus_builds(
         &self,
         talent_manager: &mut LuaTalentManager,
+        previous: &LuaTalentManager,
+        progress: &mut ProgressCounter,
         wow_class: WowClass,
         spec: &str,
         spec_index: u8,
-    ) -> Result<usize> {
+    ) -> Result<(usize, usize)> {
         let mut count = 0;
+        let mut skipped = 0;
 
         for dungeon in &self.config.dungeons {
+            if self.cancel.is_cancelled() {
+                break;
+            }
+
             let identifier = TalentIdentifier::MythicPlus {
                 dungeon: dungeon.clone(),
             };
 
             // Try primary timespan first
             let primary_timespan = MythicPlusTimespan::primary_for_today();
-            let url = self.url_builder.build_mythic_plus_url(wow_class, spec, dungeon, primary_timespan);
+            let overridden = self.url_override(wow_class, spec, ContentType::MythicPlus);
+            let url = overridden
+                .clone()
+                .unwrap_or_else(|| self.url_builder.build_mythic_plus_url(wow_class, spec, dungeon, primary_timespan));
 
-            println!("    Fetching: {} from {}", identifier.as_identifier(), url);
+            tracing::debug!(build = %identifier.as_identifier(), url = %url, "Fetching build");
 
-            let talent_string = match self.fetcher.fetch_talent_build(&url).await? {
-                Some(talent) => {
-                    println!("      Found talent build ({})", primary_timespan.as_str());
-                    Some(talent)
-                }
-                None => {
+            let fetched = match self.fetch_build(&url).await? {
+                // A pinned URL has no other timespan to fall back to
+                FetchedBuild::Missing if overridden.is_none() => {
                     // Try fallback timespan
                     let fallback_timespan = primary_timespan.fallback();
                     let fallback_url = self.url_builder.build_mythic_plus_url(
@@ -190,32 +775,605 @@ impl TalentOrchestrator {
                         fallback_timespan,
                     );
 
-                    println!("      Trying fallback: {}", fallback_timespan.as_str());
+                    tracing::debug!(
+                        build = %identifier.as_identifier(),
+                        timespan = fallback_timespan.as_str(),
+                        "Trying fallback timespan"
+                    );
 
-                    match self.fetcher.fetch_talent_build(&fallback_url).await? {
-                        Some(talent) => {
-                            println!("      Found talent build ({})", fallback_timespan.as_str());
-                            Some(talent)
-                        }
-                        None => {
-                            println!("      No talent build available");
-                            None
-                        }
+                    let fetched = self.fetch_build(&fallback_url).await?;
+                    if let FetchedBuild::Found(_) = fetched {
+                        tracing::debug!(
+                            build = %identifier.as_identifier(),
+                            timespan = fallback_timespan.as_str(),
+                            "Found talent build"
+                        );
+                    }
+                    fetched
+                }
+                fetched => {
+                    if let FetchedBuild::Found(_) = fetched {
+                        tracing::debug!(
+                            build = %identifier.as_identifier(),
+                            timespan = primary_timespan.as_str(),
+                            "Found talent build"
+                        );
                     }
+                    fetched
                 }
             };
 
-            if let Some(talent_string) = talent_string {
-                let talent = TalentLoadout::new(identifier.as_talent_name(), talent_string);
-                talent_manager.add_talent(
-                    wow_class.to_lua_format().to_string(),
-                    spec_index,
-                    talent,
-                );
-                count += 1;
+            match fetched {
+                FetchedBuild::Found(talent_string) => {
+                    let talent = TalentLoadout::new(identifier.as_talent_name(), talent_string);
+                    talent_manager.add_talent(
+                        wow_class.to_lua_format().to_string(),
+                        spec_index,
+                        talent,
+                    );
+                    count += 1;
+                }
+                FetchedBuild::Unpopular(popularity) => {
+                    tracing::info!(
+                        build = %identifier.as_identifier(),
+                        popularity,
+                        min_popularity = ?self.config.min_popularity,
+                        "Skipping unpopular talent build"
+                    );
+                    Self::keep_previous_build(
+                        talent_manager,
+                        previous,
+                        wow_class,
+                        spec_index,
+                        &identifier.as_talent_name(),
+                    );
+                    skipped += 1;
+                }
+                FetchedBuild::Missing => {
+                    tracing::info!(build = %identifier.as_identifier(), "No talent build available");
+                }
             }
+
+            self.report_progress(progress, wow_class, spec, &identifier);
+        }
+
+        Ok((count, skipped))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Character;
+    use crate::test_support::{fetcher_builder, MockResponse, MockServer, TempDir};
+
+    const SIKRAN_PATH: &str = "/frost/mage/raid/overview/heroic/sikran";
+    const FROST_BUILD: &str = "mage/frost/CcQAAAAAAAAAAAAAAAAAAAAAAAAABC123";
+
+    fn build_page(talent_string: &str) -> MockResponse {
+        MockResponse::html(&format!(
+            r#"<a href="https://www.wowhead.com/talent-calc/blizzard/{}">Build</a>"#,
+            talent_string
+        ))
+    }
+
+    /// Orchestrator for one heroic Sikran frost mage build, fetched from `server`
+    fn sikran_orchestrator(server: &MockServer, output_path: std::path::PathBuf) -> TalentOrchestrator {
+        let config = Config {
+            characters: vec![Character {
+                name: "MyMage".to_string(),
+                class: "Mage".to_string(),
+                specializations: vec!["frost".to_string()],
+                last_fetched: Default::default(),
+            }],
+            raid_difficulties: vec!["heroic".to_string()],
+            raid_bosses: vec!["sikran".to_string()],
+            dungeons: Vec::new(),
+            clear_previous_builds: false,
+            output_path,
+            backup_count: 3,
+            min_popularity: None,
+            refresh_interval_hours: None,
+            spec_url_overrides: Default::default(),
+            content_preference: Vec::new(),
+        };
+
+        TalentOrchestrator::with_fetcher(config, fetcher_builder().max_retries(0).build().unwrap())
+            .with_url_builder(ArchonUrlBuilder::with_base_url(server.url("")))
+    }
+
+    fn write_existing_sikran_build(path: &std::path::Path, talent_string: &str) {
+        let mut existing = LuaTalentManager::new();
+        existing.add_talent(
+            "MAGE".to_string(),
+            3,
+            TalentLoadout::new("R-heroic-sikran_ARCT".to_string(), talent_string.to_string()),
+        );
+        existing.write_to_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_builds_leave_file_untouched() {
+        let server = MockServer::start().await;
+        server.respond(SIKRAN_PATH, build_page(FROST_BUILD));
+
+        let dir = TempDir::new();
+        let path = dir.path().join("TalentLoadoutsEx.lua");
+        write_existing_sikran_build(&path, FROST_BUILD);
+        let before = std::fs::metadata(&path).unwrap().modified().unwrap();
+
+        let summary = sikran_orchestrator(&server, path.clone()).run().await.unwrap();
+
+        assert_eq!(server.hits(SIKRAN_PATH), 1);
+        assert_eq!(summary.builds_changed, 0);
+        assert!(!summary.file_written);
+        assert_eq!(std::fs::metadata(&path).unwrap().modified().unwrap(), before);
+        // No write means no backup either
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_redirected_build_urls_are_in_the_summary() {
+        let server = MockServer::start().await;
+        server.respond(
+            SIKRAN_PATH,
+            MockResponse::status(301).header("Location", &server.url("/frost/mage/raid/overview/heroic/sikran-new")),
+        );
+        server.respond("/frost/mage/raid/overview/heroic/sikran-new", build_page(FROST_BUILD));
+
+        let dir = TempDir::new();
+        let orchestrator = sikran_orchestrator(&server, dir.path().join("TalentLoadoutsEx.lua"));
+        let summary = orchestrator.run().await.unwrap();
+
+        assert_eq!(summary.raid_talents, 1);
+        assert_eq!(
+            summary.redirects,
+            vec![Redirect {
+                requested: server.url(SIKRAN_PATH),
+                final_url: server.url("/frost/mage/raid/overview/heroic/sikran-new"),
+            }]
+        );
+
+        // The build now comes from the memory cache, and the first run's redirect isn't repeated
+        let summary = orchestrator.run().await.unwrap();
+        assert!(summary.redirects.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_spec_url_override_is_fetched_instead_of_the_default() {
+        const FIRE_SIKRAN_PATH: &str = "/fire/mage/raid/overview/heroic/sikran";
+        const FIRE_BUILD: &str = "mage/fire/CcQAAAAAAAAAAAAAAAAAAAAAAAAXYZ789";
+        const CURATED_BUILD: &str = "mage/fire/CcQAAAAAAAAAAAAAAAAAAAAAAAACUR456";
+
+        let server = MockServer::start().await;
+        server.respond(SIKRAN_PATH, build_page(FROST_BUILD));
+        server.respond(FIRE_SIKRAN_PATH, build_page(FIRE_BUILD));
+        for timespan in ["this-week", "last-week"] {
+            let path = format!("/fire/mage/mythic-plus/overview/10//ara-kara/{}", timespan);
+            server.respond(&path, build_page(FIRE_BUILD));
+        }
+        server.respond("/curated/fire", build_page(CURATED_BUILD));
+
+        let dir = TempDir::new();
+        let overrides = BTreeMap::from([(ContentType::Raid, server.url("/curated/fire"))]);
+        let orchestrator_at = |path: std::path::PathBuf| {
+            let mut orchestrator = sikran_orchestrator(&server, path);
+            orchestrator.config.characters[0].specializations = vec!["frost".to_string(), "fire".to_string()];
+            orchestrator.config.dungeons.push("ara-kara".to_string());
+            orchestrator.config.spec_url_overrides.insert("mage/fire".to_string(), overrides.clone());
+            orchestrator
+        };
+
+        // Fire's raid builds come from the override; frost and fire's Mythic+ keep the defaults
+        let path = dir.path().join("TalentLoadoutsEx.lua");
+        let summary = orchestrator_at(path.clone()).run().await.unwrap();
+        assert_eq!((summary.raid_talents, summary.mythic_plus_talents), (2, 1));
+        assert_eq!(server.hits("/curated/fire"), 1);
+        assert_eq!(server.hits(FIRE_SIKRAN_PATH), 0);
+        assert_eq!(server.hits(SIKRAN_PATH), 1);
+
+        let written = LuaTalentManager::load_from_file(&path).unwrap();
+        let fire = written.get_spec_talents("MAGE", 2).unwrap();
+        let build_of = |name: &str| fire.iter().find(|talent| talent.name == name).unwrap().text.clone();
+        assert_eq!(build_of("R-heroic-sikran_ARCT"), CURATED_BUILD);
+        assert_eq!(build_of("M+-ara-kara_ARCT"), FIRE_BUILD);
+        let frost = written.get_spec_talents("MAGE", 3).unwrap();
+        assert!(frost.iter().all(|talent| talent.text == FROST_BUILD));
+
+        // Filling in missing overview builds uses the override too
+        let path = dir.path().join("Filled.lua");
+        orchestrator_at(path.clone()).fill_missing().await.unwrap();
+        assert_eq!(server.hits("/curated/fire"), 2);
+        assert_eq!(server.hits("/fire/mage/raid/overview"), 0);
+
+        let written = LuaTalentManager::load_from_file(&path).unwrap();
+        let fire = written.get_spec_talents("MAGE", 2).unwrap();
+        let raid_overview = fire.iter().find(|talent| talent.name == "R-overview_ARCT").unwrap();
+        assert_eq!(raid_overview.text, CURATED_BUILD);
+    }
+
+    #[tokio::test]
+    async fn test_changed_build_is_written() {
+        let server = MockServer::start().await;
+        server.respond(SIKRAN_PATH, build_page(FROST_BUILD));
+
+        let dir = TempDir::new();
+        let path = dir.path().join("TalentLoadoutsEx.lua");
+        write_existing_sikran_build(&path, "mage/frost/CcQAAAAAAAAAAAAAAAAAAAAAAAAOLD000");
+
+        let summary = sikran_orchestrator(&server, path.clone()).run().await.unwrap();
+
+        assert_eq!(summary.builds_changed, 1);
+        assert!(summary.file_written);
+        assert!(std::fs::read_to_string(&path).unwrap().contains(FROST_BUILD));
+    }
+
+    #[tokio::test]
+    async fn test_unpopular_builds_keep_the_current_one() {
+        const BROODTWISTER_PATH: &str = "/frost/mage/raid/overview/heroic/broodtwister";
+        const OLD_BUILD: &str = "mage/frost/CcQAAAAAAAAAAAAAAAAAAAAAAAAOLD000";
+        const FRINGE_BUILD: &str = "mage/frost/CcQAAAAAAAAAAAAAAAAAAAAAAAAXYZ789";
+
+        let popular_page = |talent_string: &str, popularity: u32| {
+            MockResponse::html(&format!(
+                r#"<p>Used by {}% of top players</p><a href="https://www.wowhead.com/talent-calc/blizzard/{}">Build</a>"#,
+                popularity, talent_string
+            ))
+        };
+        let server = MockServer::start().await;
+        server.respond(SIKRAN_PATH, popular_page(FROST_BUILD, 72));
+        server.respond(BROODTWISTER_PATH, popular_page(FRINGE_BUILD, 8));
+
+        let dir = TempDir::new();
+        let path = dir.path().join("TalentLoadoutsEx.lua");
+        let mut existing = LuaTalentManager::new();
+        for name in ["R-heroic-sikran_ARCT", "R-heroic-broodtwister_ARCT"] {
+            existing.add_talent(
+                "MAGE".to_string(),
+                3,
+                TalentLoadout::new(name.to_string(), OLD_BUILD.to_string()),
+            );
         }
+        existing.write_to_file(&path).unwrap();
+
+        let mut orchestrator = sikran_orchestrator(&server, path.clone());
+        orchestrator.config.raid_bosses.push("broodtwister".to_string());
+        orchestrator.config.min_popularity = Some(50.0);
+        let summary = orchestrator.run().await.unwrap();
+
+        assert_eq!(summary.builds_skipped, 1);
+        assert_eq!(summary.builds_changed, 1);
+        let written = LuaTalentManager::load_from_file(&path).unwrap();
+        let talents = written.get_spec_talents("MAGE", 3).unwrap();
+        let text_of = |name: &str| talents.iter().find(|talent| talent.name == name).map(|talent| talent.text.as_str());
+        assert_eq!(text_of("R-heroic-sikran_ARCT"), Some(FROST_BUILD));
+        assert_eq!(text_of("R-heroic-broodtwister_ARCT"), Some(OLD_BUILD));
+    }
+
+    #[tokio::test]
+    async fn test_incremental_refresh_only_fetches_stale_specs() {
+        const FIRE_SIKRAN_PATH: &str = "/fire/mage/raid/overview/heroic/sikran";
+        const FIRE_BUILD: &str = "mage/fire/CcQAAAAAAAAAAAAAAAAAAAAAAAAXYZ789";
+
+        let server = MockServer::start().await;
+        server.respond(SIKRAN_PATH, build_page(FROST_BUILD));
+        server.respond(FIRE_SIKRAN_PATH, build_page(FIRE_BUILD));
+
+        let dir = TempDir::new();
+        let path = dir.path().join("TalentLoadoutsEx.lua");
+        let config_path = dir.path().join("config.json");
+        let old_build = "mage/frost/CcQAAAAAAAAAAAAAAAAAAAAAAAAOLD000";
+        write_existing_sikran_build(&path, old_build);
+
+        let fresh = Utc::now() - chrono::Duration::hours(1);
+        let stale = Utc::now() - chrono::Duration::hours(48);
+        let mut orchestrator = sikran_orchestrator(&server, path.clone()).with_config_path(&config_path);
+        orchestrator.config.refresh_interval_hours = Some(24);
+        let character = &mut orchestrator.config.characters[0];
+        character.specializations.push("fire".to_string());
+        character.mark_fetched("frost", ContentType::Raid, fresh);
+        character.mark_fetched("fire", ContentType::Raid, stale);
+
+        let summary = orchestrator.run().await.unwrap();
+
+        assert_eq!(server.hits(SIKRAN_PATH), 0);
+        assert_eq!(server.hits(FIRE_SIKRAN_PATH), 1);
+        assert_eq!(summary.specs_up_to_date, 1);
+
+        let written = LuaTalentManager::load_from_file(&path).unwrap();
+        assert_eq!(written.get_spec_talents("MAGE", 3).unwrap()[0].text, old_build);
+        assert_eq!(written.get_spec_talents("MAGE", 2).unwrap()[0].text, FIRE_BUILD);
+
+        let saved = Config::from_file(&config_path).unwrap();
+        let last_fetched = &saved.characters[0].last_fetched;
+        assert_eq!(last_fetched["frost"][&ContentType::Raid], fresh);
+        assert!(last_fetched["fire"][&ContentType::Raid] > stale);
+    }
+
+    #[tokio::test]
+    async fn test_failed_specs_stay_stale() {
+        const FIRE_SIKRAN_PATH: &str = "/fire/mage/raid/overview/heroic/sikran";
+        const FIRE_BUILD: &str = "mage/fire/CcQAAAAAAAAAAAAAAAAAAAAAAAAXYZ789";
+
+        // Every frost fetch fails, while fire's build is found
+        let server = MockServer::start().await;
+        server.respond(SIKRAN_PATH, MockResponse::status(503));
+        server.respond(FIRE_SIKRAN_PATH, build_page(FIRE_BUILD));
+
+        let dir = TempDir::new();
+        let path = dir.path().join("TalentLoadoutsEx.lua");
+        let config_path = dir.path().join("config.json");
+
+        let stale = Utc::now() - chrono::Duration::hours(48);
+        let mut orchestrator = sikran_orchestrator(&server, path.clone()).with_config_path(&config_path);
+        orchestrator.config.refresh_interval_hours = Some(24);
+        let character = &mut orchestrator.config.characters[0];
+        character.specializations.push("fire".to_string());
+        character.mark_fetched("frost", ContentType::Raid, stale);
+        character.mark_fetched("fire", ContentType::Raid, stale);
+
+        orchestrator.run().await.unwrap();
+        assert_eq!(server.hits(SIKRAN_PATH), 1);
+
+        let saved = Config::from_file(&config_path).unwrap();
+        let last_fetched = &saved.characters[0].last_fetched;
+        assert_eq!(last_fetched["frost"][&ContentType::Raid], stale);
+        assert!(last_fetched["fire"][&ContentType::Raid] > stale);
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_body_does_not_abort_the_update() {
+        const FIRE_SIKRAN_PATH: &str = "/fire/mage/raid/overview/heroic/sikran";
+        const FIRE_BUILD: &str = "mage/fire/CcQAAAAAAAAAAAAAAAAAAAAAAAAXYZ789";
+
+        // Frost's page is over the body limit, fire's fits
+        let server = MockServer::start().await;
+        server.respond(SIKRAN_PATH, MockResponse::html(&"x".repeat(8192)));
+        server.respond(FIRE_SIKRAN_PATH, build_page(FIRE_BUILD));
+
+        let dir = TempDir::new();
+        let path = dir.path().join("TalentLoadoutsEx.lua");
+        let mut orchestrator = sikran_orchestrator(&server, path.clone());
+        orchestrator.fetcher = fetcher_builder().max_retries(0).max_body_size(4096).build().unwrap();
+        orchestrator.config.characters[0].specializations.push("fire".to_string());
+
+        let summary = orchestrator.run().await.unwrap();
+
+        assert_eq!(summary.raid_talents, 1);
+        let written = LuaTalentManager::load_from_file(&path).unwrap();
+        let fire = written.get_spec_talents("MAGE", 2).unwrap();
+        assert_eq!(fire[0].text, FIRE_BUILD);
+        assert!(written.get_spec_talents("MAGE", 3).is_none_or(|talents| talents.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn test_mythic_plus_update_keeps_raid_build() {
+        const OLD_BUILD: &str = "mage/frost/CcQAAAAAAAAAAAAAAAAAAAAAAAAOLD000";
+        const ARA_KARA_BUILD: &str = "mage/frost/CcQAAAAAAAAAAAAAAAAAAAAAAAAXYZ789";
+
+        let server = MockServer::start().await;
+        for timespan in ["this-week", "last-week"] {
+            let path = format!("/frost/mage/mythic-plus/overview/10//ara-kara/{}", timespan);
+            server.respond(&path, build_page(ARA_KARA_BUILD));
+        }
+
+        let dir = TempDir::new();
+        let path = dir.path().join("TalentLoadoutsEx.lua");
+        let mut existing = LuaTalentManager::new();
+        for name in ["R-heroic-sikran_ARCT", "M+-ara-kara_ARCT"] {
+            existing.add_talent(
+                "MAGE".to_string(),
+                3,
+                TalentLoadout::new(name.to_string(), OLD_BUILD.to_string()),
+            );
+        }
+        existing.write_to_file(&path).unwrap();
+
+        // Only Mythic+ builds are configured, so only those are refetched
+        let mut orchestrator = sikran_orchestrator(&server, path.clone());
+        orchestrator.config.raid_bosses.clear();
+        orchestrator.config.dungeons.push("ara-kara".to_string());
+        let summary = orchestrator.run().await.unwrap();
+
+        assert_eq!(summary.mythic_plus_talents, 1);
+        let written = LuaTalentManager::load_from_file(&path).unwrap();
+        let talents = written.get_spec_talents("MAGE", 3).unwrap();
+        let text_of = |name: &str| talents.iter().find(|talent| talent.name == name).map(|talent| talent.text.as_str());
+        assert_eq!(text_of("R-heroic-sikran_ARCT"), Some(OLD_BUILD));
+        assert_eq!(text_of("M+-ara-kara_ARCT"), Some(ARA_KARA_BUILD));
+    }
+
+    #[tokio::test]
+    async fn test_fill_missing_falls_back_to_the_next_preferred_content() {
+        const FIRE_BUILD: &str = "mage/fire/CcQAAAAAAAAAAAAAAAAAAAAAAAAXYZ789";
+
+        let server = MockServer::start().await;
+        server.respond("/fire/mage/mythic-plus/overview", MockResponse::status(500));
+        server.respond("/fire/mage/raid/overview", build_page(FIRE_BUILD));
+
+        // Only fire mage's Mythic+ build is missing
+        let dir = TempDir::new();
+        let path = dir.path().join("TalentLoadoutsEx.lua");
+        let mut existing = LuaTalentManager::new();
+        for spec in crate::wow::Spec::all() {
+            for name in ["R-heroic-sikran_ARCT", "M+-ara-kara_ARCT"] {
+                if *spec == crate::wow::Spec::FireMage && name.starts_with("M+") {
+                    continue;
+                }
+                let talent = TalentLoadout::new(name.to_string(), "class/spec/EXISTING".to_string());
+                existing.add_talent(spec.class().to_lua_format().to_string(), spec.index(), talent);
+            }
+        }
+        existing.write_to_file(&path).unwrap();
+
+        let mut orchestrator = sikran_orchestrator(&server, path.clone());
+        orchestrator.fetcher = fetcher_builder().max_retries(0).base_url(server.url("")).build().unwrap();
+        orchestrator.config.content_preference = vec![ContentType::MythicPlus, ContentType::Raid];
+        let summary = orchestrator.fill_missing().await.unwrap();
+
+        assert_eq!(server.hits("/fire/mage/raid/overview"), 1);
+        assert_eq!(summary.mythic_plus_talents, 1);
+        assert_eq!(
+            summary.content_fallbacks,
+            vec![ContentFallback {
+                class: "MAGE".to_string(),
+                spec: "fire".to_string(),
+                requested: ContentType::MythicPlus,
+                used: ContentType::Raid,
+            }]
+        );
+
+        let written = LuaTalentManager::load_from_file(&path).unwrap();
+        let fire = written.get_spec_talents("MAGE", 2).unwrap();
+        let overview = fire.iter().find(|talent| talent.name == "M+-overview_ARCT").unwrap();
+        assert_eq!(overview.text, FIRE_BUILD);
+    }
+
+    #[tokio::test]
+    async fn test_fill_missing_fetches_only_absent_specs() {
+        const FIRE_BUILD: &str = "mage/fire/CcQAAAAAAAAAAAAAAAAAAAAAAAAXYZ789";
+
+        let server = MockServer::start().await;
+        server.respond("/frost/mage/raid/overview", build_page(FROST_BUILD));
+        server.respond("/fire/mage/mythic-plus/overview", build_page(FIRE_BUILD));
+
+        // Every spec has both builds except frost mage (raid) and fire mage (Mythic+)
+        let dir = TempDir::new();
+        let path = dir.path().join("TalentLoadoutsEx.lua");
+        let mut existing = LuaTalentManager::new();
+        for spec in crate::wow::Spec::all() {
+            for (content, name) in [(ContentType::Raid, "R-heroic-sikran_ARCT"), (ContentType::MythicPlus, "M+-ara-kara_ARCT")] {
+                let skipped = match content {
+                    ContentType::Raid => *spec == crate::wow::Spec::FrostMage,
+                    _ => *spec == crate::wow::Spec::FireMage,
+                };
+                if !skipped {
+                    let talent = TalentLoadout::new(name.to_string(), "class/spec/EXISTING".to_string());
+                    existing.add_talent(spec.class().to_lua_format().to_string(), spec.index(), talent);
+                }
+            }
+        }
+        existing.write_to_file(&path).unwrap();
+
+        let summary = sikran_orchestrator(&server, path.clone()).fill_missing().await.unwrap();
+
+        let mut fetched = server.request_paths();
+        fetched.sort();
+        assert_eq!(fetched, vec!["/fire/mage/mythic-plus/overview", "/frost/mage/raid/overview"]);
+        assert_eq!((summary.raid_talents, summary.mythic_plus_talents), (1, 1));
+        assert!(summary.file_written);
+        assert!(summary.bytes_downloaded > 0);
+
+        let written = LuaTalentManager::load_from_file(&path).unwrap();
+        let text_of = |spec_index: u8, name: &str| {
+            written
+                .get_spec_talents("MAGE", spec_index)
+                .and_then(|talents| talents.iter().find(|talent| talent.name == name))
+                .map(|talent| talent.text.clone())
+        };
+        assert_eq!(text_of(3, "R-overview_ARCT").as_deref(), Some(FROST_BUILD));
+        assert_eq!(text_of(3, "M+-ara-kara_ARCT").as_deref(), Some("class/spec/EXISTING"));
+        assert_eq!(text_of(2, "M+-overview_ARCT").as_deref(), Some(FIRE_BUILD));
+        assert_eq!(text_of(2, "R-heroic-sikran_ARCT").as_deref(), Some("class/spec/EXISTING"));
+        assert_eq!(written.all_talents().len(), existing.all_talents().len() + 2);
+    }
+
+    #[tokio::test]
+    async fn test_output_path_set_at_runtime_is_used() {
+        let server = MockServer::start().await;
+        server.respond(SIKRAN_PATH, build_page(FROST_BUILD));
+
+        let dir = TempDir::new();
+        let configured = dir.path().join("TalentLoadoutsEx.lua");
+        let chosen = dir.path().join("elsewhere.lua");
+
+        let summary = sikran_orchestrator(&server, configured.clone())
+            .with_output_path(&chosen)
+            .run()
+            .await
+            .unwrap();
+
+        assert!(summary.file_written);
+        assert!(std::fs::read_to_string(&chosen).unwrap().contains(FROST_BUILD));
+        assert!(!configured.exists());
+
+        // A missing directory is reported before anything is fetched
+        let missing = dir.path().join("missing").join("TalentLoadoutsEx.lua");
+        let error = sikran_orchestrator(&server, configured)
+            .with_output_path(&missing)
+            .run()
+            .await
+            .unwrap_err();
+        assert!(format!("{:#}", error).contains("does not exist"), "{:#}", error);
+        assert_eq!(server.hits(SIKRAN_PATH), 1);
+    }
+
+    #[test]
+    fn test_build_progress_payload_shape() {
+        let progress = BuildProgress {
+            class: "MAGE".to_string(),
+            spec: "frost".to_string(),
+            build: "M+-ara-kara".to_string(),
+            completed: 3,
+            total: 12,
+        };
+
+        assert_eq!(
+            serde_json::to_value(&progress).unwrap(),
+            serde_json::json!({
+                "class": "MAGE",
+                "spec": "frost",
+                "build": "M+-ara-kara",
+                "completed": 3,
+                "total": 12
+            })
+        );
+    }
+
+    #[test]
+    fn test_diff_builds_flags_changed_entries() {
+        let mut existing = LuaTalentManager::new();
+        existing.add_talent("MAGE".to_string(), 3, TalentLoadout::new("R-heroic-sikran_ARCT".to_string(), "OLD".to_string()));
+        existing.add_talent("MAGE".to_string(), 3, TalentLoadout::new("M+-ara-kara_ARCT".to_string(), "SAME".to_string()));
+        existing.add_talent("MAGE".to_string(), 3, TalentLoadout::new("R-mythic-sikran_ARCT".to_string(), "GONE".to_string()));
+        existing.add_talent("MAGE".to_string(), 3, TalentLoadout::new("My build".to_string(), "MANUAL".to_string()));
+
+        let mut updated = LuaTalentManager::new();
+        updated.add_talent("MAGE".to_string(), 3, TalentLoadout::new("R-heroic-sikran_ARCT".to_string(), "NEW".to_string()));
+        updated.add_talent("MAGE".to_string(), 3, TalentLoadout::new("M+-ara-kara_ARCT".to_string(), "SAME".to_string()));
+        updated.add_talent("MAGE".to_string(), 3, TalentLoadout::new("M+-city-of-threads_ARCT".to_string(), "ADDED".to_string()));
+
+        let diffs = diff_builds(&existing, &updated);
+        let by_build = |name: &str| diffs.iter().find(|d| d.build == name).unwrap();
+
+        // Manually created talents are never touched by updates, so they aren't diffed
+        assert_eq!(diffs.len(), 4);
+        assert!(diffs.iter().all(|d| d.class == "MAGE" && d.spec == "frost"));
+
+        let changed = by_build("R-heroic-sikran_ARCT");
+        assert_eq!((changed.old.as_deref(), changed.new.as_deref()), (Some("OLD"), Some("NEW")));
+        assert!(changed.changed);
+
+        assert!(!by_build("M+-ara-kara_ARCT").changed);
+
+        let added = by_build("M+-city-of-threads_ARCT");
+        assert_eq!((added.old.as_deref(), added.new.as_deref()), (None, Some("ADDED")));
+        assert!(added.changed);
+
+        let removed = by_build("R-mythic-sikran_ARCT");
+        assert_eq!((removed.old.as_deref(), removed.new.as_deref()), (Some("GONE"), None));
+        assert!(removed.changed);
+    }
+
+    #[test]
+    fn test_total_builds_counts_every_spec() {
+        let mut config = Config::example();
+        config.raid_bosses = vec!["a".to_string(), "b".to_string()];
+        config.raid_difficulties = vec!["heroic".to_string(), "mythic".to_string()];
+        config.dungeons = vec!["c".to_string(), "d".to_string(), "e".to_string()];
+        let specs: usize = config.characters.iter().map(|c| c.specializations.len()).sum();
 
-        Ok(count)
+        let orchestrator = TalentOrchestrator::new(config);
+        assert_eq!(orchestrator.total_builds(), specs * (2 * 2 + 3));
     }
 }
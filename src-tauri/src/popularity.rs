@@ -0,0 +1,60 @@
+/// Phrases that introduce a build's popularity ("used by 62% of top players")
+const POPULARITY_MARKERS: &[&str] = &["used by", "popularity", "popular with", "usage"];
+
+/// How far past a marker the percentage may appear, in characters
+const MAX_MARKER_DISTANCE: usize = 40;
+
+/// Popularity percentage (0-100) from page text, looked for right after one
+/// of the usual phrases so unrelated percentages (talent pick rates) are ignored
+pub fn parse_popularity(text: &str) -> Option<f32> {
+    let text = text.to_lowercase();
+
+    POPULARITY_MARKERS.iter().find_map(|marker| {
+        text.match_indices(marker).find_map(|(index, _)| {
+            let after: String = text[index + marker.len()..].chars().take(MAX_MARKER_DISTANCE).collect();
+            parse_percentage(&after)
+        })
+    })
+}
+
+/// First percentage in `text`, accepting "62%", "62 %", "62.5%" and "62,5 %"
+pub fn parse_percentage(text: &str) -> Option<f32> {
+    let chars: Vec<char> = text.chars().collect();
+
+    chars.iter().enumerate().find_map(|(percent_index, &c)| {
+        if c != '%' {
+            return None;
+        }
+
+        // Walk back over whitespace (including no-break spaces) to the number
+        let end = chars[..percent_index].iter().rposition(|c| !c.is_whitespace())? + 1;
+        let start = chars[..end]
+            .iter()
+            .rposition(|c| !(c.is_ascii_digit() || *c == '.' || *c == ','))
+            .map_or(0, |position| position + 1);
+
+        let number: String = chars[start..end].iter().collect::<String>().replace(',', ".");
+        number.parse::<f32>().ok().filter(|value| (0.0..=100.0).contains(value))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_percentage_formats() {
+        assert_eq!(parse_percentage("62%"), Some(62.0));
+        assert_eq!(parse_percentage(" 62.5 %"), Some(62.5));
+        assert_eq!(parse_percentage("62,5\u{a0}%"), Some(62.5));
+        assert_eq!(parse_percentage("no percentage here"), None);
+        assert_eq!(parse_percentage("% of players"), None);
+    }
+
+    #[test]
+    fn test_parse_popularity_needs_a_marker() {
+        assert_eq!(parse_popularity("This build is used by 62% of top players"), Some(62.0));
+        assert_eq!(parse_popularity("Popularity: 48,3 %"), Some(48.3));
+        assert_eq!(parse_popularity("Ice Lance 95% pick rate"), None);
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Token bucket for one host
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Token bucket rate limiter with a separate bucket per host
+/// Each bucket holds up to `per_second` tokens and refills at `per_second` tokens a second,
+/// so a burst of `per_second` requests is allowed before requests are spaced out
+pub struct RateLimiter {
+    per_second: f64,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    /// Allow `per_second` requests a second to each host (0 is clamped to 1)
+    pub fn new(per_second: u32) -> Self {
+        Self {
+            per_second: per_second.max(1) as f64,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Wait until a request to `host` is allowed, then take its token
+    pub async fn acquire(&self, host: &str) {
+        while let Some(wait) = self.try_acquire(host) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Take a token for `host` if one is available, otherwise return how long until one is
+    fn try_acquire(&self, host: &str) -> Option<Duration> {
+        let mut buckets = self.buckets.lock().unwrap();
+        let now = Instant::now();
+        let bucket = buckets.entry(host.to_string()).or_insert(Bucket {
+            tokens: self.per_second,
+            last_refill: now,
+        });
+
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.per_second).min(self.per_second);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - bucket.tokens) / self.per_second))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_requests_beyond_the_burst_are_spaced_out() {
+        let limiter = RateLimiter::new(10);
+        let start = Instant::now();
+
+        // Ten from the full bucket, then one every 100ms
+        for _ in 0..10 {
+            limiter.acquire("archon.gg").await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(50));
+
+        for _ in 0..5 {
+            limiter.acquire("archon.gg").await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(450), "took {:?}", start.elapsed());
+    }
+
+    #[tokio::test]
+    async fn test_hosts_have_separate_buckets() {
+        let limiter = RateLimiter::new(1);
+        let start = Instant::now();
+
+        limiter.acquire("archon.gg").await;
+        limiter.acquire("wowhead.com").await;
+
+        assert!(start.elapsed() < Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,458 @@
+//! Fetcher settings the user can change, kept as JSON in the app data directory
+
+use crate::atomic_file::write_atomically;
+use crate::fetcher::{
+    parse_headers, parse_proxy, ArchonFetcherBuilder, DEFAULT_MAX_BODY_SIZE,
+    DEFAULT_MAX_CONCURRENT_REQUESTS, DEFAULT_MAX_RETRIES, DEFAULT_MEMORY_CACHE_CAPACITY,
+    DEFAULT_POOL_MAX_IDLE_PER_HOST, DEFAULT_REQUEST_TIMEOUT, DEFAULT_RETRY_BASE_DELAY,
+    DEFAULT_USER_AGENT,
+};
+use anyhow::{bail, Context, Result};
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::time::Duration;
+
+const CONCURRENCY_RANGE: RangeInclusive<usize> = 1..=50;
+const TIMEOUT_SECS_RANGE: RangeInclusive<u64> = 1..=600;
+const RATE_LIMIT_RANGE: RangeInclusive<u32> = 1..=100;
+const MAX_RETRIES_RANGE: RangeInclusive<u32> = 0..=10;
+const RETRY_BASE_DELAY_MS_RANGE: RangeInclusive<u64> = 10..=60_000;
+const MAX_TOTAL_BYTES_RANGE: RangeInclusive<usize> = 1024..=1024 * 1024 * 1024;
+const BATCH_RETRY_BUDGET_RANGE: RangeInclusive<usize> = 0..=1000;
+const STARTUP_JITTER_MS_RANGE: RangeInclusive<u64> = 0..=10_000;
+const POOL_MAX_IDLE_PER_HOST_RANGE: RangeInclusive<usize> = 0..=100;
+const POOL_IDLE_TIMEOUT_SECS_RANGE: RangeInclusive<u64> = 1..=3600;
+const MEMORY_CACHE_CAPACITY_RANGE: RangeInclusive<usize> = 0..=10_000;
+const MAX_BODY_SIZE_RANGE: RangeInclusive<usize> = 64 * 1024..=100 * 1024 * 1024;
+
+/// Settings used to build the shared fetcher
+/// Fields missing from the file keep their defaults
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FetcherSettings {
+    /// Maximum number of requests in flight at once
+    pub concurrency: usize,
+    /// Time allowed for a single request, in seconds
+    pub timeout_secs: u64,
+    /// Requests per second to each host; unset means only `concurrency` applies
+    pub rate_limit: Option<u32>,
+    /// How many times a transient failure is retried
+    pub max_retries: u32,
+    /// Delay before the first retry in milliseconds, doubled for each one after it
+    pub retry_base_delay_ms: u64,
+    pub user_agent: String,
+    /// Proxy every request goes through, e.g. "socks5://127.0.0.1:1080"
+    pub proxy: Option<String>,
+    /// Keep the raw body of every fetched page, so cached builds can be re-parsed
+    pub keep_response_bodies: bool,
+    /// Headers sent with every request (e.g. a proxy cookie), by name
+    pub headers: BTreeMap<String, String>,
+    /// Hosts requests may go to; unset only allows Archon.gg
+    pub allowed_hosts: Option<Vec<String>>,
+    /// CSS selector for talent links, for when Archon changes its markup
+    pub talent_link_selector: Option<String>,
+    /// Mirrors of Archon.gg tried in order when it fails
+    pub fallback_base_urls: Vec<String>,
+    /// Body bytes one batch may download; unset means no limit
+    pub max_total_bytes: Option<usize>,
+    /// Retries shared by all fetches of one batch; unset gives each fetch its own
+    pub batch_retry_budget: Option<usize>,
+    /// Longest random delay before each fetch of a batch, in milliseconds (0 disables it)
+    pub startup_jitter_ms: u64,
+    /// Idle connections kept open per host (0 keeps none)
+    pub pool_max_idle_per_host: usize,
+    /// Seconds an idle connection is kept open; unset keeps reqwest's default
+    pub pool_idle_timeout_secs: Option<u64>,
+    /// Parse every successful response as HTML, even if it isn't labelled as HTML
+    pub force_parse: bool,
+    /// Builds kept in memory for repeated fetches of the same URL (0 disables it)
+    pub memory_cache_capacity: usize,
+    /// Largest page body read, in bytes
+    pub max_body_size: usize,
+    /// Root build URLs are built from instead of Archon.gg; its host is allowed too
+    pub base_url: Option<String>,
+}
+
+impl Default for FetcherSettings {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_MAX_CONCURRENT_REQUESTS,
+            timeout_secs: DEFAULT_REQUEST_TIMEOUT.as_secs(),
+            rate_limit: None,
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_base_delay_ms: DEFAULT_RETRY_BASE_DELAY.as_millis() as u64,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            proxy: None,
+            keep_response_bodies: false,
+            headers: BTreeMap::new(),
+            allowed_hosts: None,
+            talent_link_selector: None,
+            fallback_base_urls: Vec::new(),
+            max_total_bytes: None,
+            batch_retry_budget: None,
+            startup_jitter_ms: 0,
+            pool_max_idle_per_host: DEFAULT_POOL_MAX_IDLE_PER_HOST,
+            pool_idle_timeout_secs: None,
+            force_parse: false,
+            memory_cache_capacity: DEFAULT_MEMORY_CACHE_CAPACITY,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            base_url: None,
+        }
+    }
+}
+
+impl FetcherSettings {
+    /// Load settings from `path`, or the defaults if it doesn't exist yet
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(path).context("Failed to read settings")?;
+        let settings: Self = serde_json::from_str(&contents).context("Failed to parse settings")?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// Validate and write the settings to `path`
+    pub fn save(&self, path: &Path) -> Result<()> {
+        self.validate()?;
+        let contents = serde_json::to_string_pretty(self)?;
+        write_atomically(path, contents).context("Failed to write settings")
+    }
+
+    /// Check every setting is within its allowed range
+    pub fn validate(&self) -> Result<()> {
+        if !CONCURRENCY_RANGE.contains(&self.concurrency) {
+            bail!(
+                "Concurrency must be between {} and {}, got {}",
+                CONCURRENCY_RANGE.start(),
+                CONCURRENCY_RANGE.end(),
+                self.concurrency
+            );
+        }
+        if !TIMEOUT_SECS_RANGE.contains(&self.timeout_secs) {
+            bail!(
+                "Timeout must be between {} and {} seconds, got {}",
+                TIMEOUT_SECS_RANGE.start(),
+                TIMEOUT_SECS_RANGE.end(),
+                self.timeout_secs
+            );
+        }
+        if let Some(rate_limit) = self.rate_limit {
+            if !RATE_LIMIT_RANGE.contains(&rate_limit) {
+                bail!(
+                    "Rate limit must be between {} and {} requests per second, got {}",
+                    RATE_LIMIT_RANGE.start(),
+                    RATE_LIMIT_RANGE.end(),
+                    rate_limit
+                );
+            }
+        }
+        if !MAX_RETRIES_RANGE.contains(&self.max_retries) {
+            bail!(
+                "Retries must be between {} and {}, got {}",
+                MAX_RETRIES_RANGE.start(),
+                MAX_RETRIES_RANGE.end(),
+                self.max_retries
+            );
+        }
+        if !RETRY_BASE_DELAY_MS_RANGE.contains(&self.retry_base_delay_ms) {
+            bail!(
+                "Retry delay must be between {} and {} ms, got {}",
+                RETRY_BASE_DELAY_MS_RANGE.start(),
+                RETRY_BASE_DELAY_MS_RANGE.end(),
+                self.retry_base_delay_ms
+            );
+        }
+        if let Some(max_total_bytes) = self.max_total_bytes {
+            if !MAX_TOTAL_BYTES_RANGE.contains(&max_total_bytes) {
+                bail!(
+                    "Batch download limit must be between {} and {} bytes, got {}",
+                    MAX_TOTAL_BYTES_RANGE.start(),
+                    MAX_TOTAL_BYTES_RANGE.end(),
+                    max_total_bytes
+                );
+            }
+        }
+        if let Some(budget) = self.batch_retry_budget {
+            if !BATCH_RETRY_BUDGET_RANGE.contains(&budget) {
+                bail!(
+                    "Batch retry budget must be between {} and {}, got {}",
+                    BATCH_RETRY_BUDGET_RANGE.start(),
+                    BATCH_RETRY_BUDGET_RANGE.end(),
+                    budget
+                );
+            }
+        }
+        if !STARTUP_JITTER_MS_RANGE.contains(&self.startup_jitter_ms) {
+            bail!(
+                "Startup jitter must be between {} and {} ms, got {}",
+                STARTUP_JITTER_MS_RANGE.start(),
+                STARTUP_JITTER_MS_RANGE.end(),
+                self.startup_jitter_ms
+            );
+        }
+        if !POOL_MAX_IDLE_PER_HOST_RANGE.contains(&self.pool_max_idle_per_host) {
+            bail!(
+                "Idle connections per host must be between {} and {}, got {}",
+                POOL_MAX_IDLE_PER_HOST_RANGE.start(),
+                POOL_MAX_IDLE_PER_HOST_RANGE.end(),
+                self.pool_max_idle_per_host
+            );
+        }
+        if let Some(timeout_secs) = self.pool_idle_timeout_secs {
+            if !POOL_IDLE_TIMEOUT_SECS_RANGE.contains(&timeout_secs) {
+                bail!(
+                    "Idle connection timeout must be between {} and {} seconds, got {}",
+                    POOL_IDLE_TIMEOUT_SECS_RANGE.start(),
+                    POOL_IDLE_TIMEOUT_SECS_RANGE.end(),
+                    timeout_secs
+                );
+            }
+        }
+        if !MEMORY_CACHE_CAPACITY_RANGE.contains(&self.memory_cache_capacity) {
+            bail!(
+                "Memory cache capacity must be between {} and {} builds, got {}",
+                MEMORY_CACHE_CAPACITY_RANGE.start(),
+                MEMORY_CACHE_CAPACITY_RANGE.end(),
+                self.memory_cache_capacity
+            );
+        }
+        if !MAX_BODY_SIZE_RANGE.contains(&self.max_body_size) {
+            bail!(
+                "Maximum page size must be between {} and {} bytes, got {}",
+                MAX_BODY_SIZE_RANGE.start(),
+                MAX_BODY_SIZE_RANGE.end(),
+                self.max_body_size
+            );
+        }
+        if self.user_agent.trim().is_empty() {
+            bail!("User agent must not be empty");
+        }
+        if reqwest::header::HeaderValue::from_str(&self.user_agent).is_err() {
+            bail!("User agent contains characters not allowed in a header: {:?}", self.user_agent);
+        }
+        if let Some(proxy) = &self.proxy {
+            parse_proxy(proxy)?;
+        }
+        self.header_map()?;
+        for host in self.allowed_hosts.iter().flatten() {
+            let parsed = reqwest::Url::parse(&format!("http://{}/", host.trim())).ok();
+            if parsed.as_ref().and_then(reqwest::Url::host_str) != Some(host.trim().to_lowercase().as_str()) {
+                bail!("Allowed host must be a host name without scheme or port, got {:?}", host);
+            }
+        }
+        if let Some(css) = &self.talent_link_selector {
+            if scraper::Selector::parse(css).is_err() {
+                bail!("Talent link selector is not a valid CSS selector: {:?}", css);
+            }
+        }
+        if let Some(url) = &self.base_url {
+            check_web_url("Base URL", url)?;
+        }
+        for url in &self.fallback_base_urls {
+            check_web_url("Fallback base URL", url)?;
+        }
+
+        Ok(())
+    }
+
+    /// `headers` as sent with each request
+    fn header_map(&self) -> Result<HeaderMap> {
+        parse_headers(self.headers.iter().map(|(name, value)| (name.as_str(), value.as_str())))
+    }
+
+    /// Configure `builder` with these settings
+    /// Headers that fail `validate` are left out
+    pub fn apply(&self, builder: ArchonFetcherBuilder) -> ArchonFetcherBuilder {
+        let builder = builder
+            .default_headers(self.header_map().unwrap_or_default())
+            .concurrency(self.concurrency)
+            .timeout(Duration::from_secs(self.timeout_secs))
+            .max_retries(self.max_retries)
+            .retry_base_delay(Duration::from_millis(self.retry_base_delay_ms))
+            .user_agent(self.user_agent.clone());
+        let builder = match self.rate_limit {
+            Some(per_second) => builder.rate_limit(per_second),
+            None => builder,
+        };
+        let builder = match &self.allowed_hosts {
+            Some(hosts) => builder.allowed_hosts(hosts.clone()),
+            None => builder,
+        };
+        let builder = match &self.talent_link_selector {
+            Some(css) => builder.talent_link_selector(css.clone()),
+            None => builder,
+        };
+        let builder = match &self.base_url {
+            Some(url) => builder.base_url(url.clone()),
+            None => builder,
+        };
+        let builder = builder.fallback_base_urls(self.fallback_base_urls.clone());
+        let builder = match self.max_total_bytes {
+            Some(bytes) => builder.max_total_bytes(bytes),
+            None => builder,
+        };
+        let builder = match self.batch_retry_budget {
+            Some(retries) => builder.batch_retry_budget(retries),
+            None => builder,
+        };
+        let builder = builder
+            .startup_jitter(Duration::from_millis(self.startup_jitter_ms))
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .force_parse(self.force_parse)
+            .memory_cache_capacity(self.memory_cache_capacity)
+            .max_body_size(self.max_body_size);
+        let builder = match self.pool_idle_timeout_secs {
+            Some(secs) => builder.pool_idle_timeout(Duration::from_secs(secs)),
+            None => builder,
+        };
+
+        match &self.proxy {
+            Some(proxy) => builder.proxy(proxy.clone()),
+            None => builder,
+        }
+    }
+}
+
+/// Fail unless `url` is an http(s) URL with a host, naming the `setting` it came from
+fn check_web_url(setting: &str, url: &str) -> Result<()> {
+    let parsed = reqwest::Url::parse(url).ok();
+    if !parsed.is_some_and(|parsed| matches!(parsed.scheme(), "http" | "https") && parsed.host_str().is_some()) {
+        bail!("{} must be an http or https URL, got {:?}", setting, url);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_settings_round_trip() {
+        let settings = FetcherSettings {
+            concurrency: 12,
+            timeout_secs: 30,
+            rate_limit: Some(4),
+            max_retries: 5,
+            retry_base_delay_ms: 250,
+            user_agent: "MyUpdater/2.0".to_string(),
+            proxy: Some("socks5h://127.0.0.1:1080".to_string()),
+            keep_response_bodies: true,
+            headers: BTreeMap::from([("Cookie".to_string(), "session=abc".to_string())]),
+            allowed_hosts: Some(vec!["archon.gg".to_string(), "mirror.example.com".to_string()]),
+            talent_link_selector: Some("a.copy-build".to_string()),
+            fallback_base_urls: vec!["https://archive.example.com/archon".to_string()],
+            max_total_bytes: Some(50_000_000),
+            batch_retry_budget: Some(20),
+            startup_jitter_ms: 250,
+            pool_max_idle_per_host: 2,
+            pool_idle_timeout_secs: Some(90),
+            force_parse: true,
+            memory_cache_capacity: 128,
+            max_body_size: 1024 * 1024,
+            base_url: Some("http://127.0.0.1:8080".to_string()),
+        };
+
+        let json = serde_json::to_value(&settings).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "concurrency": 12,
+                "timeoutSecs": 30,
+                "rateLimit": 4,
+                "maxRetries": 5,
+                "retryBaseDelayMs": 250,
+                "userAgent": "MyUpdater/2.0",
+                "proxy": "socks5h://127.0.0.1:1080",
+                "keepResponseBodies": true,
+                "headers": { "Cookie": "session=abc" },
+                "allowedHosts": ["archon.gg", "mirror.example.com"],
+                "talentLinkSelector": "a.copy-build",
+                "fallbackBaseUrls": ["https://archive.example.com/archon"],
+                "maxTotalBytes": 50_000_000,
+                "batchRetryBudget": 20,
+                "startupJitterMs": 250,
+                "poolMaxIdlePerHost": 2,
+                "poolIdleTimeoutSecs": 90,
+                "forceParse": true,
+                "memoryCacheCapacity": 128,
+                "maxBodySize": 1_048_576,
+                "baseUrl": "http://127.0.0.1:8080"
+            })
+        );
+        assert_eq!(serde_json::from_value::<FetcherSettings>(json).unwrap(), settings);
+
+        let dir = TempDir::new();
+        let path = dir.path().join("fetcher_settings.json");
+        assert_eq!(FetcherSettings::load(&path).unwrap(), FetcherSettings::default());
+        settings.save(&path).unwrap();
+        assert_eq!(FetcherSettings::load(&path).unwrap(), settings);
+    }
+
+    #[test]
+    fn test_settings_out_of_range_are_rejected() {
+        let with = |change: fn(&mut FetcherSettings)| {
+            let mut settings = FetcherSettings::default();
+            change(&mut settings);
+            settings.validate()
+        };
+
+        assert!(with(|_| {}).is_ok());
+        assert!(with(|s| s.concurrency = 50).is_ok());
+        assert!(with(|s| s.concurrency = 0).is_err());
+        assert!(with(|s| s.concurrency = 51).is_err());
+        assert!(with(|s| s.timeout_secs = 0).is_err());
+        assert!(with(|s| s.rate_limit = Some(0)).is_err());
+        assert!(with(|s| s.max_retries = 0).is_ok());
+        assert!(with(|s| s.max_retries = 11).is_err());
+        assert!(with(|s| s.retry_base_delay_ms = 0).is_err());
+        assert!(with(|s| s.retry_base_delay_ms = 60_001).is_err());
+        assert!(with(|s| s.user_agent = " ".to_string()).is_err());
+        assert!(with(|s| s.user_agent = "bad\nagent".to_string()).is_err());
+        assert!(with(|s| s.proxy = Some("http://proxy.local:3128".to_string())).is_ok());
+        assert!(with(|s| s.proxy = Some("ftp://proxy.local".to_string())).is_err());
+        assert!(with(|s| s.proxy = Some("not a url".to_string())).is_err());
+        assert!(with(|s| {
+            s.headers.insert("Bad Header".to_string(), "x".to_string());
+        })
+        .is_err());
+        assert!(with(|s| {
+            s.headers.insert("X-Token".to_string(), "bad\nvalue".to_string());
+        })
+        .is_err());
+        assert!(with(|s| s.allowed_hosts = Some(vec!["Archon.gg".to_string()])).is_ok());
+        assert!(with(|s| s.allowed_hosts = Some(vec!["https://archon.gg".to_string()])).is_err());
+        assert!(with(|s| s.allowed_hosts = Some(vec!["archon.gg:443".to_string()])).is_err());
+        assert!(with(|s| s.allowed_hosts = Some(vec![String::new()])).is_err());
+        assert!(with(|s| s.talent_link_selector = Some("a[href*='talent-calc']".to_string())).is_ok());
+        assert!(with(|s| s.talent_link_selector = Some("a[href*=".to_string())).is_err());
+        assert!(with(|s| s.base_url = Some("https://mirror.example.com/wow/builds".to_string())).is_ok());
+        assert!(with(|s| s.base_url = Some("mirror.example.com".to_string())).is_err());
+        assert!(with(|s| s.fallback_base_urls = vec!["http://127.0.0.1:8080".to_string()]).is_ok());
+        assert!(with(|s| s.fallback_base_urls = vec!["archive.example.com".to_string()]).is_err());
+        assert!(with(|s| s.fallback_base_urls = vec!["file:///saved/pages".to_string()]).is_err());
+        assert!(with(|s| s.max_total_bytes = Some(1024)).is_ok());
+        assert!(with(|s| s.max_total_bytes = Some(0)).is_err());
+        assert!(with(|s| s.batch_retry_budget = Some(0)).is_ok());
+        assert!(with(|s| s.batch_retry_budget = Some(1001)).is_err());
+        assert!(with(|s| s.startup_jitter_ms = 10_000).is_ok());
+        assert!(with(|s| s.startup_jitter_ms = 10_001).is_err());
+        assert!(with(|s| s.pool_max_idle_per_host = 0).is_ok());
+        assert!(with(|s| s.pool_max_idle_per_host = 101).is_err());
+        assert!(with(|s| s.pool_idle_timeout_secs = Some(0)).is_err());
+        assert!(with(|s| s.memory_cache_capacity = 0).is_ok());
+        assert!(with(|s| s.memory_cache_capacity = 10_001).is_err());
+        assert!(with(|s| s.max_body_size = 1024).is_err());
+
+        // Fields left out of the file keep their defaults
+        let partial: FetcherSettings = serde_json::from_str(r#"{"concurrency": 8}"#).unwrap();
+        assert_eq!(partial.concurrency, 8);
+        assert_eq!(partial.user_agent, DEFAULT_USER_AGENT);
+    }
+}
@@ -0,0 +1,336 @@
+//! Decoder for Blizzard's talent loadout export strings (the code wowhead and
+//! the in-game import dialog use).
+//!
+//! The string is base64 over a little-endian bitstream:
+//! - 8 bits serialization version
+//! - 16 bits spec ID
+//! - 128 bits tree hash (16 bytes)
+//! - then, for every node of the class tree in the client's node order:
+//!   - 1 bit "selected"
+//!   - if selected and version >= 2: 1 bit "purchased" (unpurchased nodes are granted for free)
+//!   - if purchased: 1 bit "partially ranked", followed by 6 bits ranks purchased when set
+//!   - then 1 bit "choice node", followed by 2 bits zero-based choice entry when set
+//!
+//! The node order comes from the game client, so nodes are identified by their
+//! position in that order rather than by trait node ID.
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BITS_PER_CHAR: usize = 6;
+
+const BIT_WIDTH_VERSION: usize = 8;
+const BIT_WIDTH_SPEC_ID: usize = 16;
+const TREE_HASH_BYTES: usize = 16;
+const BIT_WIDTH_RANKS_PURCHASED: usize = 6;
+const BIT_WIDTH_CHOICE_INDEX: usize = 2;
+
+/// Newest serialization version this decoder understands (The War Within)
+const MAX_SUPPORTED_VERSION: u8 = 2;
+
+/// A talent node that is selected in a loadout
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelectedNode {
+    /// Position of the node in the class tree's node order
+    pub id: usize,
+    /// Ranks purchased; only encoded for partially ranked nodes, `None` means all ranks
+    pub rank: Option<u8>,
+    /// Whether only some of the node's ranks are purchased
+    pub partial: bool,
+    /// Zero-based entry picked on a choice node
+    pub choice: Option<u8>,
+    /// Granted for free (e.g. by the spec) rather than purchased
+    pub granted: bool,
+}
+
+/// A decoded talent loadout
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TalentLoadout {
+    pub version: u8,
+    pub spec_id: u16,
+    pub tree_hash: [u8; TREE_HASH_BYTES],
+    pub nodes: Vec<SelectedNode>,
+}
+
+/// A node selected in both loadouts, but with a different rank, choice or grant
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeChange {
+    pub id: usize,
+    pub old: SelectedNode,
+    pub new: SelectedNode,
+}
+
+/// What changed between two loadouts, sorted by node position
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum LoadoutDiff {
+    /// The loadouts belong to different specs, so their nodes can't be compared
+    #[serde(rename_all = "camelCase")]
+    SpecChanged { old_spec_id: u16, new_spec_id: u16 },
+    Nodes {
+        /// Selected only in the new loadout
+        added: Vec<SelectedNode>,
+        /// Selected only in the old loadout
+        removed: Vec<SelectedNode>,
+        changed: Vec<NodeChange>,
+    },
+}
+
+/// Compare the selected nodes of two loadouts of the same spec
+pub fn diff_loadouts(old: &TalentLoadout, new: &TalentLoadout) -> LoadoutDiff {
+    if old.spec_id != new.spec_id {
+        return LoadoutDiff::SpecChanged {
+            old_spec_id: old.spec_id,
+            new_spec_id: new.spec_id,
+        };
+    }
+
+    let mut nodes: BTreeMap<usize, (Option<&SelectedNode>, Option<&SelectedNode>)> = BTreeMap::new();
+    for node in &old.nodes {
+        nodes.entry(node.id).or_default().0 = Some(node);
+    }
+    for node in &new.nodes {
+        nodes.entry(node.id).or_default().1 = Some(node);
+    }
+
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let mut changed = Vec::new();
+    for (id, pair) in nodes {
+        match pair {
+            (None, Some(new)) => added.push(new.clone()),
+            (Some(old), None) => removed.push(old.clone()),
+            (Some(old), Some(new)) if old != new => changed.push(NodeChange {
+                id,
+                old: old.clone(),
+                new: new.clone(),
+            }),
+            _ => {}
+        }
+    }
+
+    LoadoutDiff::Nodes { added, removed, changed }
+}
+
+/// Decode a talent export string (just the code, without "class/spec/")
+pub fn decode(code: &str) -> Result<TalentLoadout> {
+    let mut reader = BitReader::new(code)?;
+
+    let version = reader.read(BIT_WIDTH_VERSION)? as u8;
+    if version == 0 || version > MAX_SUPPORTED_VERSION {
+        bail!("Unsupported talent string version {}", version);
+    }
+
+    let spec_id = reader.read(BIT_WIDTH_SPEC_ID)? as u16;
+
+    let mut tree_hash = [0u8; TREE_HASH_BYTES];
+    for byte in tree_hash.iter_mut() {
+        *byte = reader.read(8)? as u8;
+    }
+
+    let mut nodes = Vec::new();
+    let mut id = 0;
+
+    // Trailing padding bits decode as unselected nodes, so reading until the
+    // stream runs out is safe
+    while reader.remaining() > 0 {
+        if reader.read(1)? == 1 {
+            nodes.push(read_selected_node(&mut reader, version, id)?);
+        }
+        id += 1;
+    }
+
+    Ok(TalentLoadout {
+        version,
+        spec_id,
+        tree_hash,
+        nodes,
+    })
+}
+
+fn read_selected_node(reader: &mut BitReader, version: u8, id: usize) -> Result<SelectedNode> {
+    let purchased = version < 2 || reader.read(1)? == 1;
+
+    let mut node = SelectedNode {
+        id,
+        rank: None,
+        partial: false,
+        choice: None,
+        granted: !purchased,
+    };
+
+    if purchased {
+        if reader.read(1)? == 1 {
+            node.partial = true;
+            node.rank = Some(reader.read(BIT_WIDTH_RANKS_PURCHASED)? as u8);
+        }
+
+        if reader.read(1)? == 1 {
+            node.choice = Some(reader.read(BIT_WIDTH_CHOICE_INDEX)? as u8);
+        }
+    }
+
+    Ok(node)
+}
+
+/// Little-endian bit reader over base64 characters
+struct BitReader {
+    values: Vec<u8>,
+    position: usize,
+}
+
+impl BitReader {
+    fn new(code: &str) -> Result<Self> {
+        let values = code
+            .trim()
+            .bytes()
+            .map(|c| match BASE64_ALPHABET.iter().position(|&a| a == c) {
+                Some(value) => Ok(value as u8),
+                None => bail!("Invalid character {:?} in talent string", c as char),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            values,
+            position: 0,
+        })
+    }
+
+    fn remaining(&self) -> usize {
+        self.values.len() * BITS_PER_CHAR - self.position
+    }
+
+    fn read(&mut self, width: usize) -> Result<u32> {
+        if width > self.remaining() {
+            bail!("Talent string ended unexpectedly");
+        }
+
+        let mut value = 0;
+        for i in 0..width {
+            let char_value = self.values[self.position / BITS_PER_CHAR];
+            let bit = (char_value >> (self.position % BITS_PER_CHAR)) & 1;
+            value |= (bit as u32) << i;
+            self.position += 1;
+        }
+
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Frost Mage (spec 64), version 2, zero tree hash, with nodes:
+    /// 0 fully ranked, 1 unselected, 2 partially ranked (1 rank),
+    /// 3 choice node with the second entry, 4 granted
+    const HAND_BUILT_CODE: &str = "CAEAAAAAAAAAAAAAAAAAAAAAAMegtA";
+
+    #[test]
+    fn test_decode_header() {
+        let loadout = decode(HAND_BUILT_CODE).unwrap();
+        assert_eq!(loadout.version, 2);
+        assert_eq!(loadout.spec_id, 64);
+        assert_eq!(loadout.tree_hash, [0; 16]);
+    }
+
+    #[test]
+    fn test_decode_nodes() {
+        let loadout = decode(HAND_BUILT_CODE).unwrap();
+        assert_eq!(loadout.nodes.len(), 4);
+
+        let ids: Vec<_> = loadout.nodes.iter().map(|n| n.id).collect();
+        assert_eq!(ids, vec![0, 2, 3, 4]);
+
+        assert_eq!(loadout.nodes[0].rank, None);
+        assert!(!loadout.nodes[0].partial);
+
+        assert!(loadout.nodes[1].partial);
+        assert_eq!(loadout.nodes[1].rank, Some(1));
+
+        assert_eq!(loadout.nodes[2].choice, Some(1));
+
+        assert!(loadout.nodes[3].granted);
+    }
+
+    #[test]
+    fn test_decode_partial_rank_before_choice() {
+        // Node 0 has 2 ranks purchased; node 1 is fully ranked with the third
+        // choice entry, so misreading the partial flag or rank width shifts it
+        let loadout = decode("CAEAAAAAAAAAAAAAAAAAAAAAAcBr").unwrap();
+        assert_eq!(loadout.nodes.len(), 2);
+
+        assert!(loadout.nodes[0].partial);
+        assert_eq!(loadout.nodes[0].rank, Some(2));
+        assert_eq!(loadout.nodes[0].choice, None);
+
+        assert_eq!(loadout.nodes[1].id, 1);
+        assert!(!loadout.nodes[1].partial);
+        assert_eq!(loadout.nodes[1].rank, None);
+        assert_eq!(loadout.nodes[1].choice, Some(2));
+    }
+
+    #[test]
+    fn test_diff_loadouts_lists_the_one_change() {
+        let old = decode(HAND_BUILT_CODE).unwrap();
+        // The hand-built code with a second rank bought in node 2
+        let new = decode("CAEAAAAAAAAAAAAAAAAAAAAAAMugtA").unwrap();
+        assert_eq!(new.nodes[1].rank, Some(2));
+
+        assert_eq!(
+            diff_loadouts(&old, &new),
+            LoadoutDiff::Nodes {
+                added: Vec::new(),
+                removed: Vec::new(),
+                changed: vec![NodeChange {
+                    id: 2,
+                    old: old.nodes[1].clone(),
+                    new: new.nodes[1].clone(),
+                }],
+            }
+        );
+
+        // The partial-rank code selects nodes 0 and 1 of the same spec
+        let other = decode("CAEAAAAAAAAAAAAAAAAAAAAAAcBr").unwrap();
+        let LoadoutDiff::Nodes { added, removed, changed } = diff_loadouts(&old, &other) else {
+            panic!("same spec");
+        };
+        assert_eq!(added.iter().map(|n| n.id).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(removed.iter().map(|n| n.id).collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert_eq!(changed.iter().map(|c| c.id).collect::<Vec<_>>(), vec![0]);
+    }
+
+    #[test]
+    fn test_diff_loadouts_of_different_specs() {
+        let old = decode(HAND_BUILT_CODE).unwrap();
+        let new = TalentLoadout { spec_id: 63, ..old.clone() };
+
+        assert_eq!(
+            diff_loadouts(&old, &new),
+            LoadoutDiff::SpecChanged { old_spec_id: 64, new_spec_id: 63 }
+        );
+    }
+
+    #[test]
+    fn test_decode_empty_loadout() {
+        // Header only: every remaining bit is padding
+        let loadout = decode("CAEAAAAAAAAAAAAAAAAAAAAAAA").unwrap();
+        assert_eq!(loadout.spec_id, 64);
+        assert!(loadout.nodes.is_empty());
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_input() {
+        assert!(decode("").is_err());
+        assert!(decode("CAEAAAA").is_err());
+        assert!(decode("CAEAAAAAAAAAAAAAAAAAAAAAA-A").is_err());
+        // Version 3 is not known yet
+        assert!(decode("DAEAAAAAAAAAAAAAAAAAAAAAAA").is_err());
+    }
+}
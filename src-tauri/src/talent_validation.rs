@@ -0,0 +1,160 @@
+use crate::archon::ContentType;
+use crate::fetcher::{is_valid_talent_code, TalentBuild};
+use crate::lua_talent::LuaTalentManager;
+use crate::talent_codec;
+use crate::wow::Spec;
+use anyhow::Result;
+use serde::Serialize;
+use std::path::Path;
+
+/// Which loadout in a talent file a validation result belongs to
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpecKey {
+    pub class_name: String,
+    pub spec_index: u8,
+    pub name: String,
+}
+
+/// Whether a loadout's talent string can be imported by the game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum ValidationResult {
+    Valid,
+    /// Why the string would be rejected
+    Invalid(String),
+}
+
+/// Check every talent string in a TalentLoadoutsEx.lua file, sorted by class,
+/// spec and then file order
+pub fn validate_config(path: impl AsRef<Path>) -> Result<Vec<(SpecKey, ValidationResult)>> {
+    let manager = LuaTalentManager::load_from_file(path)?;
+    Ok(validate_talents(&manager))
+}
+
+/// Check every talent string held by `manager`
+pub fn validate_talents(manager: &LuaTalentManager) -> Vec<(SpecKey, ValidationResult)> {
+    let mut results: Vec<_> = manager
+        .all_talents()
+        .into_iter()
+        .map(|(class_name, spec_index, talent)| {
+            let key = SpecKey {
+                class_name: class_name.to_string(),
+                spec_index,
+                name: talent.name.clone(),
+            };
+            (key, validate_talent_string(&talent.text))
+        })
+        .collect();
+
+    // Stable, so loadouts of one spec keep their file order
+    results.sort_by(|(a, _), (b, _)| (&a.class_name, a.spec_index).cmp(&(&b.class_name, b.spec_index)));
+    results
+}
+
+/// Every spec, crossed with `content`, that has no auto-generated build in `manager`,
+/// in `Spec::all` and then `content` order. `name` holds the content type's path
+/// segment (e.g. "mythic-plus"). Content types whose builds carry no name prefix
+/// (PvP) can't be told apart and are skipped
+pub fn missing_specs(manager: &LuaTalentManager, content: &[ContentType]) -> Vec<SpecKey> {
+    Spec::all()
+        .iter()
+        .flat_map(|spec| content.iter().map(move |content| (spec, content)))
+        .filter_map(|(spec, content)| {
+            let prefix = content.talent_name_prefix()?;
+            let class_name = spec.class().to_lua_format();
+            let has_build = manager
+                .get_spec_talents(class_name, spec.index())
+                .is_some_and(|talents| {
+                    talents.iter().any(|t| t.is_auto_generated() && t.name.starts_with(prefix))
+                });
+
+            (!has_build).then(|| SpecKey {
+                class_name: class_name.to_string(),
+                spec_index: spec.index(),
+                name: content.as_path().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Check a "class/spec/code" string: the code must look like an export string
+/// and decode with the talent decoder
+pub fn validate_talent_string(talent_string: &str) -> ValidationResult {
+    let code = TalentBuild::from_talent_string(talent_string).encoded;
+
+    if !is_valid_talent_code(&code) {
+        return ValidationResult::Invalid("Not a talent export string".to_string());
+    }
+
+    match talent_codec::decode(&code) {
+        Ok(_) => ValidationResult::Valid,
+        Err(e) => ValidationResult::Invalid(format!("Failed to decode: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lua_talent::TalentLoadout;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_validate_config_flags_corrupt_entry() {
+        let dir = TempDir::new();
+        let path = dir.path().join("TalentLoadoutsEx.lua");
+        std::fs::write(
+            &path,
+            r#"TalentLoadoutEx = {
+  ["MAGE"] = {
+    [3] = {
+      { ["icon"] = 0, ["name"] = "Good", ["text"] = "mage/frost/CAEAAAAAAAAAAAAAAAAAAAAAAAMegtA" },
+      { ["icon"] = 0, ["name"] = "Corrupt", ["text"] = "mage/frost/CcQAA!AAAAAA" },
+    },
+  },
+}"#,
+        )
+        .unwrap();
+
+        let results = validate_config(&path).unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0.name, "Good");
+        assert_eq!(results[0].1, ValidationResult::Valid);
+        assert_eq!(results[1].0.name, "Corrupt");
+        assert!(matches!(results[1].1, ValidationResult::Invalid(_)));
+    }
+
+    #[test]
+    fn test_missing_specs_reports_the_gaps() {
+        let mut manager = LuaTalentManager::new();
+        for spec in Spec::all() {
+            if matches!(spec, Spec::FrostMage | Spec::HavocDemonHunter) {
+                continue;
+            }
+            let talent = TalentLoadout::new("R-heroic-sikran_ARCT".to_string(), "class/spec/CODE".to_string());
+            manager.add_talent(spec.class().to_lua_format().to_string(), spec.index(), talent);
+        }
+        // A hand-made loadout doesn't count as a build
+        let own = TalentLoadout::new("My frost build".to_string(), "class/spec/CODE".to_string());
+        manager.add_talent("MAGE".to_string(), 3, own);
+
+        let missing = missing_specs(&manager, &[ContentType::Raid, ContentType::Pvp]);
+        assert_eq!(
+            missing,
+            vec![
+                SpecKey { class_name: "MAGE".to_string(), spec_index: 3, name: "raid".to_string() },
+                SpecKey { class_name: "DEMONHUNTER".to_string(), spec_index: 1, name: "raid".to_string() },
+            ]
+        );
+
+        // None of them has a Mythic+ build
+        assert_eq!(missing_specs(&manager, &[ContentType::MythicPlus]).len(), Spec::all().len());
+    }
+
+    #[test]
+    fn test_undecodable_code_is_invalid() {
+        // Well-formed base64, but serialization version 0
+        let result = validate_talent_string("mage/frost/AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA");
+        assert!(matches!(result, ValidationResult::Invalid(reason) if reason.contains("decode")));
+    }
+}
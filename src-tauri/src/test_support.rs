@@ -0,0 +1,306 @@
+//! Test helpers: a minimal HTTP server that serves canned responses,
+//! a self-cleaning temporary directory and a `tracing` subscriber that
+//! records events
+
+use crate::fetcher::{ArchonFetcher, ArchonFetcherBuilder};
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Host `MockServer` listens on, which fetchers don't allow by default
+pub const MOCK_SERVER_HOST: &str = "127.0.0.1";
+
+/// Fetcher builder allowed to contact `MockServer`
+pub fn fetcher_builder() -> ArchonFetcherBuilder {
+    ArchonFetcher::builder().allowed_hosts([MOCK_SERVER_HOST])
+}
+
+/// A canned HTTP response served by `MockServer`
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    delay: Duration,
+}
+
+impl MockResponse {
+    /// A 200 response with an HTML body
+    pub fn html(body: &str) -> Self {
+        Self {
+            status: 200,
+            headers: vec![("Content-Type".to_string(), "text/html; charset=utf-8".to_string())],
+            body: body.as_bytes().to_vec(),
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// An empty response with the given status code
+    pub fn status(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// Add a response header
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Replace the body with raw bytes (e.g. a compressed payload)
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Wait before sending the response
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+/// A request received by `MockServer`
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub path: String,
+    /// Header names are lowercased
+    pub headers: HashMap<String, String>,
+}
+
+impl RecordedRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_lowercase()).map(String::as_str)
+    }
+}
+
+type Routes = Arc<Mutex<HashMap<String, VecDeque<MockResponse>>>>;
+type Requests = Arc<Mutex<Vec<RecordedRequest>>>;
+
+/// HTTP/1.1 server on a random local port
+/// Each path has a queue of responses; the last queued response keeps being served
+pub struct MockServer {
+    base_url: String,
+    routes: Routes,
+    requests: Requests,
+}
+
+impl MockServer {
+    /// Bind to a random port and start serving in the background
+    pub async fn start() -> Self {
+        Self::start_at("127.0.0.1:0".parse().unwrap()).await
+    }
+
+    /// Bind to `addr` (e.g. a port a client already failed to reach) and start serving
+    pub async fn start_at(addr: SocketAddr) -> Self {
+        let listener = TcpListener::bind(addr).await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let routes: Routes = Arc::default();
+        let requests: Requests = Arc::default();
+
+        let (server_routes, server_requests) = (routes.clone(), requests.clone());
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(Self::handle(stream, server_routes.clone(), server_requests.clone()));
+            }
+        });
+
+        Self { base_url, routes, requests }
+    }
+
+    /// Queue a response for the given path
+    pub fn respond(&self, path: &str, response: MockResponse) {
+        self.routes
+            .lock()
+            .unwrap()
+            .entry(path.to_string())
+            .or_default()
+            .push_back(response);
+    }
+
+    /// Requests received for the given path, oldest first
+    pub fn requests(&self, path: &str) -> Vec<RecordedRequest> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|request| request.path == path)
+            .cloned()
+            .collect()
+    }
+
+    /// Paths of all requests received, oldest first
+    pub fn request_paths(&self) -> Vec<String> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|request| request.path.clone())
+            .collect()
+    }
+
+    /// Number of requests received for the given path
+    pub fn hits(&self, path: &str) -> usize {
+        self.requests(path).len()
+    }
+
+    /// Absolute URL for a path on this server
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    async fn handle(mut stream: TcpStream, routes: Routes, requests: Requests) {
+        let mut buffer = Vec::new();
+        let mut chunk = [0u8; 1024];
+        while !buffer.windows(4).any(|w| w == b"\r\n\r\n") {
+            match stream.read(&mut chunk).await {
+                Ok(0) | Err(_) => return,
+                Ok(n) => buffer.extend_from_slice(&chunk[..n]),
+            }
+        }
+
+        let head = String::from_utf8_lossy(&buffer).to_string();
+        let mut lines = head.lines();
+        let path = lines
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .unwrap_or("/")
+            .to_string();
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+            .collect();
+
+        requests.lock().unwrap().push(RecordedRequest {
+            path: path.clone(),
+            headers,
+        });
+
+        let response = {
+            let mut routes = routes.lock().unwrap();
+            match routes.get_mut(&path) {
+                Some(queue) if queue.len() > 1 => queue.pop_front(),
+                Some(queue) => queue.front().cloned(),
+                None => None,
+            }
+        };
+        let response = response.unwrap_or_else(|| MockResponse::status(404));
+
+        tokio::time::sleep(response.delay).await;
+
+        let mut raw = format!("HTTP/1.1 {} Mock\r\n", response.status);
+        for (name, value) in &response.headers {
+            raw.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        raw.push_str(&format!("Content-Length: {}\r\nConnection: close\r\n\r\n", response.body.len()));
+
+        let mut bytes = raw.into_bytes();
+        bytes.extend_from_slice(&response.body);
+        let _ = stream.write_all(&bytes).await;
+        let _ = stream.shutdown().await;
+    }
+}
+
+/// A unique directory under the system temp dir, removed on drop
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new() -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "talent-heron-test-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        std::fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+/// An event recorded by `CapturingSubscriber`
+#[derive(Debug, Clone)]
+pub struct CapturedEvent {
+    pub level: Level,
+    pub message: String,
+    pub fields: HashMap<String, String>,
+}
+
+/// Subscriber that records every event, for asserting on log output
+/// Install it for the current thread with `tracing::subscriber::set_default`
+#[derive(Clone, Default)]
+pub struct CapturingSubscriber {
+    events: Arc<Mutex<Vec<CapturedEvent>>>,
+    next_span_id: Arc<AtomicUsize>,
+}
+
+impl CapturingSubscriber {
+    pub fn events(&self) -> Vec<CapturedEvent> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+impl Subscriber for CapturingSubscriber {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, _span: &Attributes<'_>) -> Id {
+        Id::from_u64(self.next_span_id.fetch_add(1, Ordering::SeqCst) as u64 + 1)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut captured = CapturedEvent {
+            level: *event.metadata().level(),
+            message: String::new(),
+            fields: HashMap::new(),
+        };
+        event.record(&mut captured);
+        self.events.lock().unwrap().push(captured);
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+impl Visit for CapturedEvent {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.record_str(field, &format!("{:?}", value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.fields.insert(field.name().to_string(), value.to_string());
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Bulk import of build URLs from a text file
+//!
+//! Two formats are accepted, and may be mixed:
+//! - CSV rows of `class,spec,content,url` (an optional header row is skipped)
+//! - one URL per line
+//!
+//! Blank lines and lines starting with `#` are ignored. Quoted CSV fields
+//! containing commas are not supported; none of the columns need them.
+
+use crate::archon::ContentType;
+use crate::wow::{normalize_name, WowClass};
+use anyhow::{anyhow, bail, Context, Result};
+use std::path::Path;
+
+/// Hosts build URLs must point at
+const ARCHON_HOSTS: &[&str] = &["www.archon.gg", "archon.gg"];
+
+/// Columns of a CSV row, in order
+const CSV_COLUMNS: [&str; 4] = ["class", "spec", "content", "url"];
+
+/// A build URL to fetch, with its class, spec and content when the file gave them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildRequest {
+    pub class: Option<WowClass>,
+    pub spec: Option<String>,
+    pub content: Option<ContentType>,
+    pub url: String,
+}
+
+/// Load build requests from a CSV or newline-delimited URL file
+/// Fails on the first malformed line, naming its line number
+pub fn load_urls_from_file(path: impl AsRef<Path>) -> Result<Vec<BuildRequest>> {
+    let contents = std::fs::read_to_string(path.as_ref()).context("Failed to read URL file")?;
+    parse_urls(&contents)
+}
+
+/// Parse the contents of a URL file, see `load_urls_from_file`
+pub fn parse_urls(contents: &str) -> Result<Vec<BuildRequest>> {
+    contents
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .filter(|(_, line)| !is_header(line))
+        .map(|(number, line)| parse_line(line).with_context(|| format!("Line {}", number)))
+        .collect()
+}
+
+fn is_header(line: &str) -> bool {
+    let fields: Vec<String> = split_csv(line).map(|field| field.to_lowercase()).collect();
+    fields == CSV_COLUMNS
+}
+
+fn parse_line(line: &str) -> Result<BuildRequest> {
+    if !line.contains(',') {
+        return Ok(BuildRequest {
+            class: None,
+            spec: None,
+            content: None,
+            url: validate_url(line)?,
+        });
+    }
+
+    let fields: Vec<&str> = split_csv(line).collect();
+    let [class, spec, content, url] = fields.as_slice() else {
+        bail!("Expected {} columns ({}), found {}", CSV_COLUMNS.len(), CSV_COLUMNS.join(", "), fields.len());
+    };
+
+    let wow_class = WowClass::from_name(class).ok_or_else(|| anyhow!("Unknown class: {}", class))?;
+    let spec = normalize_name(spec);
+    if wow_class.spec_index(&spec).is_none() {
+        bail!("Unknown spec '{}' for class {}", spec, wow_class.to_url_format());
+    }
+    let content = ContentType::from_str(content).ok_or_else(|| anyhow!("Unknown content type: {}", content))?;
+
+    Ok(BuildRequest {
+        class: Some(wow_class),
+        spec: Some(spec),
+        content: Some(content),
+        url: validate_url(url)?,
+    })
+}
+
+fn split_csv(line: &str) -> impl Iterator<Item = &str> {
+    line.split(',').map(|field| field.trim().trim_matches('"').trim())
+}
+
+/// Check that `url` is an http(s) link to Archon.gg
+fn validate_url(url: &str) -> Result<String> {
+    let parsed = reqwest::Url::parse(url).with_context(|| format!("Invalid URL: {}", url))?;
+
+    if !matches!(parsed.scheme(), "http" | "https") {
+        bail!("Not a web URL: {}", url);
+    }
+    if !parsed.host_str().is_some_and(|host| ARCHON_HOSTS.contains(&host)) {
+        bail!("Not an Archon.gg URL: {}", url);
+    }
+
+    Ok(url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FROST_URL: &str = "https://www.archon.gg/wow/builds/frost/mage/raid/overview";
+    const FURY_URL: &str = "https://www.archon.gg/wow/builds/fury/warrior/mythic-plus/overview";
+
+    #[test]
+    fn test_parse_csv_and_url_list() {
+        let contents = format!(
+            "class,spec,content,url\n\
+             # raid builds\n\
+             Mage, Frost, raid, {}\n\
+             \n\
+             {}\n",
+            FROST_URL, FURY_URL
+        );
+
+        let requests = parse_urls(&contents).unwrap();
+
+        assert_eq!(
+            requests,
+            vec![
+                BuildRequest {
+                    class: Some(WowClass::Mage),
+                    spec: Some("frost".to_string()),
+                    content: Some(ContentType::Raid),
+                    url: FROST_URL.to_string(),
+                },
+                BuildRequest {
+                    class: None,
+                    spec: None,
+                    content: None,
+                    url: FURY_URL.to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_malformed_row_names_its_line() {
+        let error = parse_urls(&format!("mage,frost,raid,{}\nmage,frost,{}\n", FROST_URL, FROST_URL)).unwrap_err();
+        assert!(format!("{:#}", error).starts_with("Line 2: Expected 4 columns"));
+
+        let error = parse_urls("https://example.com/wow/builds/frost/mage/raid/overview").unwrap_err();
+        assert!(format!("{:#}", error).contains("Not an Archon.gg URL"));
+
+        assert!(parse_urls(&format!("mage,fury,raid,{}", FROST_URL)).is_err());
+    }
+
+    #[test]
+    fn test_load_urls_from_file() {
+        let dir = crate::test_support::TempDir::new();
+        let path = dir.path().join("urls.txt");
+        std::fs::write(&path, format!("{}\n", FROST_URL)).unwrap();
+
+        let requests = load_urls_from_file(&path).unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(load_urls_from_file(dir.path().join("missing.txt")).is_err());
+    }
+}
@@ -39,6 +39,40 @@ impl WowClass {
         }
     }
 
+    /// All playable classes
+    pub fn all() -> &'static [WowClass] {
+        &[
+            Self::Warrior,
+            Self::Paladin,
+            Self::Hunter,
+            Self::Rogue,
+            Self::Priest,
+            Self::DeathKnight,
+            Self::Shaman,
+            Self::Mage,
+            Self::Warlock,
+            Self::Monk,
+            Self::Druid,
+            Self::DemonHunter,
+            Self::Evoker,
+        ]
+    }
+
+    /// Parse class from a loosely formatted name
+    /// Accepts PascalCase, URL format and spaced names ("DeathKnight", "death-knight", "Death Knight")
+    pub fn from_name(s: &str) -> Option<Self> {
+        let wanted = normalize_name(s).replace('-', "");
+        Self::all()
+            .iter()
+            .copied()
+            .find(|class| class.to_url_format().replace('-', "") == wanted)
+    }
+
+    /// Parse class from the uppercase Lua format (e.g. "DEATHKNIGHT")
+    pub fn from_lua_format(s: &str) -> Option<Self> {
+        Self::all().iter().copied().find(|class| class.to_lua_format() == s)
+    }
+
     /// Convert class to URL-safe format for Archon.gg
     /// Most classes use lowercase, but DeathKnight and DemonHunter use hyphens
     pub fn to_url_format(&self) -> &'static str {
@@ -90,101 +124,278 @@ impl WowClass {
         self.get_spec_map().keys().copied().collect()
     }
 
+    /// All specializations of this class, in in-game order
+    pub fn specs(&self) -> Vec<Spec> {
+        Spec::all().iter().copied().filter(|spec| spec.class() == *self).collect()
+    }
+
     /// Internal helper to get the spec name -> index mapping
     fn get_spec_map(&self) -> HashMap<&'static str, u8> {
+        self.specs().into_iter().map(|spec| (spec.name(), spec.index())).collect()
+    }
+}
+
+/// WoW specialization, one variant per class/spec pair
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Spec {
+    ArmsWarrior,
+    FuryWarrior,
+    ProtectionWarrior,
+    HolyPaladin,
+    ProtectionPaladin,
+    RetributionPaladin,
+    BeastMasteryHunter,
+    MarksmanshipHunter,
+    SurvivalHunter,
+    AssassinationRogue,
+    OutlawRogue,
+    SubtletyRogue,
+    DisciplinePriest,
+    HolyPriest,
+    ShadowPriest,
+    BloodDeathKnight,
+    FrostDeathKnight,
+    UnholyDeathKnight,
+    ElementalShaman,
+    EnhancementShaman,
+    RestorationShaman,
+    ArcaneMage,
+    FireMage,
+    FrostMage,
+    AfflictionWarlock,
+    DemonologyWarlock,
+    DestructionWarlock,
+    BrewmasterMonk,
+    MistweaverMonk,
+    WindwalkerMonk,
+    BalanceDruid,
+    FeralDruid,
+    GuardianDruid,
+    RestorationDruid,
+    HavocDemonHunter,
+    VengeanceDemonHunter,
+    DevastationEvoker,
+    PreservationEvoker,
+    AugmentationEvoker,
+}
+
+impl Spec {
+    /// Every retail specialization, grouped by class in in-game order
+    pub fn all() -> &'static [Spec] {
+        use Spec::*;
+        &[
+            ArmsWarrior,
+            FuryWarrior,
+            ProtectionWarrior,
+            HolyPaladin,
+            ProtectionPaladin,
+            RetributionPaladin,
+            BeastMasteryHunter,
+            MarksmanshipHunter,
+            SurvivalHunter,
+            AssassinationRogue,
+            OutlawRogue,
+            SubtletyRogue,
+            DisciplinePriest,
+            HolyPriest,
+            ShadowPriest,
+            BloodDeathKnight,
+            FrostDeathKnight,
+            UnholyDeathKnight,
+            ElementalShaman,
+            EnhancementShaman,
+            RestorationShaman,
+            ArcaneMage,
+            FireMage,
+            FrostMage,
+            AfflictionWarlock,
+            DemonologyWarlock,
+            DestructionWarlock,
+            BrewmasterMonk,
+            MistweaverMonk,
+            WindwalkerMonk,
+            BalanceDruid,
+            FeralDruid,
+            GuardianDruid,
+            RestorationDruid,
+            HavocDemonHunter,
+            VengeanceDemonHunter,
+            DevastationEvoker,
+            PreservationEvoker,
+            AugmentationEvoker,
+        ]
+    }
+
+    /// Class this spec belongs to
+    pub fn class(&self) -> WowClass {
+        use Spec::*;
         match self {
-            Self::Warrior => {
-                let mut map = HashMap::new();
-                map.insert("arms", 1);
-                map.insert("fury", 2);
-                map.insert("protection", 3);
-                map
-            }
-            Self::Paladin => {
-                let mut map = HashMap::new();
-                map.insert("holy", 1);
-                map.insert("protection", 2);
-                map.insert("retribution", 3);
-                map
-            }
-            Self::Hunter => {
-                let mut map = HashMap::new();
-                map.insert("beast-mastery", 1);
-                map.insert("marksmanship", 2);
-                map.insert("survival", 3);
-                map
-            }
-            Self::Rogue => {
-                let mut map = HashMap::new();
-                map.insert("assassination", 1);
-                map.insert("combat", 2);
-                map.insert("subtlety", 3);
-                map
-            }
-            Self::Priest => {
-                let mut map = HashMap::new();
-                map.insert("discipline", 1);
-                map.insert("holy", 2);
-                map.insert("shadow", 3);
-                map
-            }
-            Self::DeathKnight => {
-                let mut map = HashMap::new();
-                map.insert("blood", 1);
-                map.insert("frost", 2);
-                map.insert("unholy", 3);
-                map
-            }
-            Self::Shaman => {
-                let mut map = HashMap::new();
-                map.insert("elemental", 1);
-                map.insert("enhancement", 2);
-                map.insert("restoration", 3);
-                map
-            }
-            Self::Mage => {
-                let mut map = HashMap::new();
-                map.insert("arcane", 1);
-                map.insert("fire", 2);
-                map.insert("frost", 3);
-                map
-            }
-            Self::Warlock => {
-                let mut map = HashMap::new();
-                map.insert("affliction", 1);
-                map.insert("demonology", 2);
-                map.insert("destruction", 3);
-                map
-            }
-            Self::Monk => {
-                let mut map = HashMap::new();
-                map.insert("brewmaster", 1);
-                map.insert("mistweaver", 2);
-                map.insert("windwalker", 3);
-                map
-            }
-            Self::Druid => {
-                let mut map = HashMap::new();
-                map.insert("balance", 1);
-                map.insert("feral", 2);
-                map.insert("guardian", 3);
-                map.insert("restoration", 4);
-                map
-            }
-            Self::DemonHunter => {
-                let mut map = HashMap::new();
-                map.insert("havoc", 1);
-                map.insert("vengeance", 2);
-                map
-            }
-            Self::Evoker => {
-                let mut map = HashMap::new();
-                map.insert("devastation", 1);
-                map.insert("preservation", 2);
-                map
-            }
+            ArmsWarrior | FuryWarrior | ProtectionWarrior => WowClass::Warrior,
+            HolyPaladin | ProtectionPaladin | RetributionPaladin => WowClass::Paladin,
+            BeastMasteryHunter | MarksmanshipHunter | SurvivalHunter => WowClass::Hunter,
+            AssassinationRogue | OutlawRogue | SubtletyRogue => WowClass::Rogue,
+            DisciplinePriest | HolyPriest | ShadowPriest => WowClass::Priest,
+            BloodDeathKnight | FrostDeathKnight | UnholyDeathKnight => WowClass::DeathKnight,
+            ElementalShaman | EnhancementShaman | RestorationShaman => WowClass::Shaman,
+            ArcaneMage | FireMage | FrostMage => WowClass::Mage,
+            AfflictionWarlock | DemonologyWarlock | DestructionWarlock => WowClass::Warlock,
+            BrewmasterMonk | MistweaverMonk | WindwalkerMonk => WowClass::Monk,
+            BalanceDruid | FeralDruid | GuardianDruid | RestorationDruid => WowClass::Druid,
+            HavocDemonHunter | VengeanceDemonHunter => WowClass::DemonHunter,
+            DevastationEvoker | PreservationEvoker | AugmentationEvoker => WowClass::Evoker,
         }
     }
+
+    /// Spec name in URL format (e.g. "beast-mastery")
+    pub fn name(&self) -> &'static str {
+        use Spec::*;
+        match self {
+            ArmsWarrior => "arms",
+            FuryWarrior => "fury",
+            ProtectionWarrior | ProtectionPaladin => "protection",
+            HolyPaladin | HolyPriest => "holy",
+            RetributionPaladin => "retribution",
+            BeastMasteryHunter => "beast-mastery",
+            MarksmanshipHunter => "marksmanship",
+            SurvivalHunter => "survival",
+            AssassinationRogue => "assassination",
+            OutlawRogue => "outlaw",
+            SubtletyRogue => "subtlety",
+            DisciplinePriest => "discipline",
+            ShadowPriest => "shadow",
+            BloodDeathKnight => "blood",
+            FrostDeathKnight | FrostMage => "frost",
+            UnholyDeathKnight => "unholy",
+            ElementalShaman => "elemental",
+            EnhancementShaman => "enhancement",
+            RestorationShaman | RestorationDruid => "restoration",
+            ArcaneMage => "arcane",
+            FireMage => "fire",
+            AfflictionWarlock => "affliction",
+            DemonologyWarlock => "demonology",
+            DestructionWarlock => "destruction",
+            BrewmasterMonk => "brewmaster",
+            MistweaverMonk => "mistweaver",
+            WindwalkerMonk => "windwalker",
+            BalanceDruid => "balance",
+            FeralDruid => "feral",
+            GuardianDruid => "guardian",
+            HavocDemonHunter => "havoc",
+            VengeanceDemonHunter => "vengeance",
+            DevastationEvoker => "devastation",
+            PreservationEvoker => "preservation",
+            AugmentationEvoker => "augmentation",
+        }
+    }
+
+    /// Blizzard's specialization ID, as encoded in talent export strings
+    pub fn blizzard_id(&self) -> u16 {
+        use Spec::*;
+        match self {
+            ArmsWarrior => 71,
+            FuryWarrior => 72,
+            ProtectionWarrior => 73,
+            HolyPaladin => 65,
+            ProtectionPaladin => 66,
+            RetributionPaladin => 70,
+            BeastMasteryHunter => 253,
+            MarksmanshipHunter => 254,
+            SurvivalHunter => 255,
+            AssassinationRogue => 259,
+            OutlawRogue => 260,
+            SubtletyRogue => 261,
+            DisciplinePriest => 256,
+            HolyPriest => 257,
+            ShadowPriest => 258,
+            BloodDeathKnight => 250,
+            FrostDeathKnight => 251,
+            UnholyDeathKnight => 252,
+            ElementalShaman => 262,
+            EnhancementShaman => 263,
+            RestorationShaman => 264,
+            ArcaneMage => 62,
+            FireMage => 63,
+            FrostMage => 64,
+            AfflictionWarlock => 265,
+            DemonologyWarlock => 266,
+            DestructionWarlock => 267,
+            BrewmasterMonk => 268,
+            MistweaverMonk => 270,
+            WindwalkerMonk => 269,
+            BalanceDruid => 102,
+            FeralDruid => 103,
+            GuardianDruid => 104,
+            RestorationDruid => 105,
+            HavocDemonHunter => 577,
+            VengeanceDemonHunter => 581,
+            DevastationEvoker => 1467,
+            PreservationEvoker => 1468,
+            AugmentationEvoker => 1473,
+        }
+    }
+
+    /// Spec with the given Blizzard specialization ID
+    #[allow(dead_code)]
+    pub fn from_blizzard_id(id: u16) -> Option<Self> {
+        Self::all().iter().copied().find(|spec| spec.blizzard_id() == id)
+    }
+
+    /// 1-based specialization index within the class, as used in the Lua file
+    pub fn index(&self) -> u8 {
+        let class = self.class();
+        let position = Spec::all()
+            .iter()
+            .filter(|spec| spec.class() == class)
+            .position(|spec| spec == self)
+            .expect("every spec is listed in Spec::all");
+        position as u8 + 1
+    }
+
+    /// Look up a spec by class and URL-format spec name
+    pub fn from_class_and_name(class: WowClass, name: &str) -> Option<Self> {
+        let name = normalize_name(name);
+        class.specs().into_iter().find(|spec| spec.name() == name)
+    }
+
+    /// Archon.gg path segment, spec first (e.g. "frost/mage")
+    #[allow(dead_code)]
+    pub fn as_archon_path(&self) -> String {
+        format!("{}/{}", self.name(), self.class().to_url_format())
+    }
+
+    /// Wowhead talent calculator path segment, class first (e.g. "mage/frost")
+    pub fn as_wowhead_path(&self) -> String {
+        format!("{}/{}", self.class().to_url_format(), self.name())
+    }
+}
+
+impl std::str::FromStr for Spec {
+    type Err = anyhow::Error;
+
+    /// Parse "mage/frost" (class first) or "frost/mage" (spec first)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (first, second) = s
+            .split_once('/')
+            .ok_or_else(|| anyhow::anyhow!("Expected class/spec, got '{}'", s))?;
+
+        let spec = match (WowClass::from_name(first), WowClass::from_name(second)) {
+            (Some(class), _) => Self::from_class_and_name(class, second),
+            (None, Some(class)) => Self::from_class_and_name(class, first),
+            (None, None) => None,
+        };
+
+        spec.ok_or_else(|| anyhow::anyhow!("Unknown class/spec combination: '{}'", s))
+    }
+}
+
+/// Normalize a class or spec name to URL format (e.g. "Beast Mastery" -> "beast-mastery")
+pub fn normalize_name(s: &str) -> String {
+    s.trim()
+        .to_lowercase()
+        .split(|c: char| c.is_whitespace() || c == '_' || c == '-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
 }
 
 #[cfg(test)]
@@ -205,6 +416,22 @@ mod tests {
         assert_eq!(WowClass::from_str("InvalidClass"), None);
     }
 
+    #[test]
+    fn test_parse_class_from_name() {
+        assert_eq!(WowClass::from_name("mage"), Some(WowClass::Mage));
+        assert_eq!(WowClass::from_name("Death Knight"), Some(WowClass::DeathKnight));
+        assert_eq!(WowClass::from_name("demon-hunter"), Some(WowClass::DemonHunter));
+        assert_eq!(WowClass::from_name("DemonHunter"), Some(WowClass::DemonHunter));
+        assert_eq!(WowClass::from_name("bard"), None);
+    }
+
+    #[test]
+    fn test_normalize_name() {
+        assert_eq!(normalize_name("Beast Mastery"), "beast-mastery");
+        assert_eq!(normalize_name(" beast_mastery "), "beast-mastery");
+        assert_eq!(normalize_name("frost"), "frost");
+    }
+
     #[test]
     fn test_class_to_url_format() {
         assert_eq!(WowClass::Warrior.to_url_format(), "warrior");
@@ -214,6 +441,8 @@ mod tests {
 
     #[test]
     fn test_class_to_lua_format() {
+        assert_eq!(WowClass::from_lua_format("DEATHKNIGHT"), Some(WowClass::DeathKnight));
+        assert_eq!(WowClass::from_lua_format("OPTION"), None);
         assert_eq!(WowClass::Warrior.to_lua_format(), "WARRIOR");
         assert_eq!(WowClass::DeathKnight.to_lua_format(), "DEATHKNIGHT");
         assert_eq!(WowClass::DemonHunter.to_lua_format(), "DEMONHUNTER");
@@ -241,6 +470,38 @@ mod tests {
         assert_eq!(WowClass::Druid.spec_index("restoration"), Some(4));
     }
 
+    #[test]
+    fn test_all_specs() {
+        assert_eq!(Spec::all().len(), 39);
+        for class in WowClass::all() {
+            assert!(!class.specs().is_empty());
+        }
+        assert_eq!(WowClass::Rogue.spec_index("outlaw"), Some(2));
+        assert_eq!(WowClass::Evoker.spec_index("augmentation"), Some(3));
+    }
+
+    #[test]
+    fn test_blizzard_ids_are_unique() {
+        for spec in Spec::all() {
+            assert_eq!(Spec::from_blizzard_id(spec.blizzard_id()), Some(*spec));
+        }
+        assert_eq!(Spec::FrostMage.blizzard_id(), 64);
+        assert_eq!(Spec::from_blizzard_id(0), None);
+    }
+
+    #[test]
+    fn test_spec_string_round_trip() {
+        for spec in [Spec::FrostMage, Spec::BeastMasteryHunter, Spec::UnholyDeathKnight, Spec::HavocDemonHunter] {
+            assert_eq!(spec.as_archon_path().parse::<Spec>().unwrap(), spec);
+            assert_eq!(spec.as_wowhead_path().parse::<Spec>().unwrap(), spec);
+        }
+
+        assert_eq!(Spec::FrostMage.as_archon_path(), "frost/mage");
+        assert_eq!(Spec::FrostDeathKnight.as_wowhead_path(), "death-knight/frost");
+        assert!("mage/holy".parse::<Spec>().is_err());
+        assert!("frost".parse::<Spec>().is_err());
+    }
+
     #[test]
     fn test_valid_specs() {
         let warrior_specs = WowClass::Warrior.valid_specs();
@@ -13,6 +13,48 @@ pub struct DiscoveredCharacter {
     pub account_id: String,
 }
 
+/// Operating systems with a known default WoW install location
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Windows,
+    MacOs,
+    /// Installed through Wine
+    Linux,
+    Other,
+}
+
+impl Platform {
+    /// The platform this build targets
+    pub fn current() -> Self {
+        if cfg!(target_os = "windows") {
+            Self::Windows
+        } else if cfg!(target_os = "macos") {
+            Self::MacOs
+        } else if cfg!(target_os = "linux") {
+            Self::Linux
+        } else {
+            Self::Other
+        }
+    }
+
+    /// Where Battle.net installs retail WoW on this platform, most common first
+    /// `home` is the user's home directory, needed for Wine prefixes
+    pub fn install_candidates(&self, home: Option<&Path>) -> Vec<PathBuf> {
+        match self {
+            Self::Windows => vec![
+                PathBuf::from("C:\\Program Files (x86)\\World of Warcraft\\_retail_"),
+                PathBuf::from("C:\\Program Files\\World of Warcraft\\_retail_"),
+            ],
+            Self::MacOs => vec![PathBuf::from("/Applications/World of Warcraft/_retail_")],
+            Self::Linux => home
+                .map(|home| home.join(".wine/drive_c/Program Files (x86)/World of Warcraft/_retail_"))
+                .into_iter()
+                .collect(),
+            Self::Other => Vec::new(),
+        }
+    }
+}
+
 /// Scanner for finding WoW installation and characters
 pub struct WowScanner {
     wow_path: PathBuf,
@@ -28,43 +70,39 @@ impl WowScanner {
 
     /// Find the default WoW installation path based on the platform
     pub fn find_default_wow_path() -> Option<PathBuf> {
-        #[cfg(target_os = "macos")]
-        {
-            let path = PathBuf::from("/Applications/World of Warcraft/_retail_");
-            if path.exists() {
-                return Some(path);
-            }
-        }
-
-        #[cfg(target_os = "windows")]
-        {
-            let paths = vec![
-                PathBuf::from("C:\\Program Files (x86)\\World of Warcraft\\_retail_"),
-                PathBuf::from("C:\\Program Files\\World of Warcraft\\_retail_"),
-            ];
-            for path in paths {
-                if path.exists() {
-                    return Some(path);
-                }
-            }
-        }
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        Platform::current()
+            .install_candidates(home.as_deref())
+            .into_iter()
+            .find(|path| path.exists())
+    }
 
-        #[cfg(target_os = "linux")]
-        {
-            if let Ok(home) = std::env::var("HOME") {
-                let path = PathBuf::from(home)
-                    .join(".wine/drive_c/Program Files (x86)/World of Warcraft/_retail_");
-                if path.exists() {
-                    return Some(path);
-                }
-            }
-        }
+    /// Best-effort lookup of the TalentLoadoutsEx.lua file in the default installation
+    /// Prefers the first account that already has one, then the first account at all
+    /// (the addon creates the file on its first save)
+    pub fn find_talent_loadouts_path() -> Option<PathBuf> {
+        let scanner = Self::new(Self::find_default_wow_path()?);
+        let mut accounts: Vec<String> = fs::read_dir(scanner.wow_path.join("WTF").join("Account"))
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| name != "SavedVariables")
+            .collect();
+        accounts.sort();
 
-        None
+        let candidates: Vec<PathBuf> = accounts
+            .iter()
+            .map(|account_id| scanner.get_talent_loadouts_path(account_id))
+            .collect();
+        candidates
+            .iter()
+            .find(|path| path.exists())
+            .or_else(|| candidates.first())
+            .cloned()
     }
 
     /// Get the path to TalentLoadoutsEx.lua for a specific account
-    #[allow(dead_code)]
     pub fn get_talent_loadouts_path(&self, account_id: &str) -> PathBuf {
         self.wow_path
             .join("WTF")
@@ -189,6 +227,39 @@ mod tests {
         // This test will pass on systems with WoW installed
         let path = WowScanner::find_default_wow_path();
         // We can't assert it exists since it depends on the system
-        println!("Default WoW path: {:?}", path);
+        tracing::debug!(?path, "Default WoW path");
+    }
+
+    #[test]
+    fn test_install_candidates_per_platform() {
+        let home = Path::new("/home/player");
+
+        assert_eq!(
+            Platform::Windows.install_candidates(Some(home)),
+            vec![
+                PathBuf::from("C:\\Program Files (x86)\\World of Warcraft\\_retail_"),
+                PathBuf::from("C:\\Program Files\\World of Warcraft\\_retail_"),
+            ]
+        );
+        assert_eq!(
+            Platform::MacOs.install_candidates(None),
+            vec![PathBuf::from("/Applications/World of Warcraft/_retail_")]
+        );
+        assert_eq!(
+            Platform::Linux.install_candidates(Some(home)),
+            vec![home.join(".wine/drive_c/Program Files (x86)/World of Warcraft/_retail_")]
+        );
+        // Without a home directory there is no Wine prefix to look in
+        assert!(Platform::Linux.install_candidates(None).is_empty());
+        assert!(Platform::Other.install_candidates(Some(home)).is_empty());
+    }
+
+    #[test]
+    fn test_talent_loadouts_path() {
+        let scanner = WowScanner::new("/wow/_retail_");
+        assert_eq!(
+            scanner.get_talent_loadouts_path("12345#1"),
+            PathBuf::from("/wow/_retail_/WTF/Account/12345#1/SavedVariables/TalentLoadoutsEx.lua")
+        );
     }
 }